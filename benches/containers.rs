@@ -0,0 +1,108 @@
+//! Compares the throughput of the container backends with many dispensers reserving from
+//! the same container of a coffee maker while a replenisher refills it. It goes through
+//! the dispense path of the coffee makers, with a virtual clock so their dispense and
+//! replenish times don't wait. The containers print every dispense, so the results are
+//! printed to the standard error.
+//!
+//! cargo bench --bench containers [dispenses per dispenser] > /dev/null
+
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tp1::{
+    clock::Clock, config::CoffeeMakerConfig, container::ContainerBackend, containers::Containers,
+    ingredients::Ingredient,
+};
+
+const DISPENSERS: [u32; 6] = [1, 2, 4, 8, 16, 64];
+const DISPENSES: u32 = 10_000;
+const CAPACITY: u32 = 10_000;
+const REFILL: u32 = 1_000;
+
+/// Returns the containers of a coffee maker with the backend received, whose coffee
+/// container starts full.
+fn get_containers(backend: ContainerBackend) -> Containers {
+    let mut config = CoffeeMakerConfig {
+        container_backend: backend,
+        ..Default::default()
+    };
+    config
+        .containers
+        .insert(Ingredient::Coffee.name().to_owned(), CAPACITY);
+    config
+        .capacities
+        .insert(Ingredient::Coffee.name().to_owned(), CAPACITY);
+    Containers::with_clock(&config, Clock::simulated())
+}
+
+/// Makes every dispenser reserve one unit of coffee at a time until it took the dispenses
+/// received, while a replenisher refills it. Returns the time it took.
+fn run(containers: Containers, dispensers: u32, dispenses: u32) -> Duration {
+    let finished = Arc::new(AtomicBool::new(false));
+    let replenisher_containers = containers.clone();
+    let replenisher_finished = finished.clone();
+    let replenisher = thread::spawn(move || {
+        while !replenisher_finished.load(Ordering::Relaxed) {
+            let _ =
+                replenisher_containers
+                    .clone()
+                    .replenish_ingredient(&Ingredient::Coffee, REFILL, 0);
+            thread::yield_now();
+        }
+    });
+
+    let start = Instant::now();
+    let mut handles = Vec::new();
+    for dispenser_id in 0..dispensers {
+        let containers = containers.clone();
+        handles.push(thread::spawn(move || {
+            let mut taken = 0;
+            while taken < dispenses {
+                match containers.reserve(&[(Ingredient::Coffee, 1)], dispenser_id, 0) {
+                    Ok(_) => taken += 1,
+                    Err(_) => thread::yield_now(),
+                }
+            }
+        }));
+    }
+    for handle in handles {
+        let _ = handle.join();
+    }
+    let elapsed = start.elapsed();
+
+    finished.store(true, Ordering::Relaxed);
+    let _ = replenisher.join();
+    elapsed
+}
+
+fn main() {
+    let dispenses = env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse::<u32>().ok())
+        .unwrap_or(DISPENSES);
+
+    eprintln!(
+        "{:>10} {:>8} {:>12} {:>14}",
+        "DISPENSERS", "BACKEND", "ELAPSED MS", "DISPENSES/S"
+    );
+    for dispensers in DISPENSERS {
+        for backend in [ContainerBackend::Locked, ContainerBackend::Atomic] {
+            let elapsed = run(get_containers(backend), dispensers, dispenses);
+            let total = dispensers as f64 * dispenses as f64;
+            eprintln!(
+                "{:>10} {:>8} {:>12} {:>14.0}",
+                dispensers,
+                format!("{:?}", backend).to_lowercase(),
+                elapsed.as_millis(),
+                total / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
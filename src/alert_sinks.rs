@@ -0,0 +1,374 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{alerts::Alert, errors::Error};
+
+const HTTP_PREFIX: &str = "http://";
+const HTTP_PORT: u16 = 80;
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A destination of the alerts of the containers of a coffee maker.
+pub trait AlertSink: Send + Sync {
+    /// Sends the alert received, returns an error if it can't.
+    fn send(&self, alert: &Alert) -> Result<(), Error>;
+}
+
+/// Shows the alerts in the standard output.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl AlertSink for StdoutSink {
+    fn send(&self, alert: &Alert) -> Result<(), Error> {
+        println!("\n{}\n", alert);
+        Ok(())
+    }
+}
+
+/// Appends the alerts to a file, one per line. The file is created if it doesn't exist.
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    path: String,
+}
+
+impl FileSink {
+    /// Creates a new [`FileSink`] that appends to the file of the path received.
+    pub fn new(path: &str) -> FileSink {
+        FileSink {
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl AlertSink for FileSink {
+    fn send(&self, alert: &Alert) -> Result<(), Error> {
+        let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        else {
+            return Err(Error::CantSendAlert(self.path.clone()));
+        };
+        match writeln!(file, "{}", alert) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::CantSendAlert(self.path.clone())),
+        }
+    }
+}
+
+/// Writes every alert as a line of json to the Unix socket of a path, connecting to it
+/// for every alert.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct UnixSocketSink {
+    path: String,
+}
+
+#[cfg(unix)]
+impl UnixSocketSink {
+    /// Creates a new [`UnixSocketSink`] that writes to the socket of the path received.
+    pub fn new(path: &str) -> UnixSocketSink {
+        UnixSocketSink {
+            path: path.to_owned(),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AlertSink for UnixSocketSink {
+    fn send(&self, alert: &Alert) -> Result<(), Error> {
+        let Ok(line) = serde_json::to_string(alert) else {
+            return Err(Error::CantSendAlert(self.path.clone()));
+        };
+        let Ok(mut socket) = std::os::unix::net::UnixStream::connect(&self.path) else {
+            return Err(Error::CantSendAlert(self.path.clone()));
+        };
+        let _ = socket.set_write_timeout(Some(SEND_TIMEOUT));
+        match writeln!(socket, "{}", line) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::CantSendAlert(self.path.clone())),
+        }
+    }
+}
+
+/// Posts every alert as json to an http url of the local machine. An answer without a
+/// 2xx status is an error. Its host is resolved again for every alert, so an alert is
+/// posted to the address the host has when it is sent, as long as it is still local.
+#[derive(Debug, Clone)]
+pub struct HttpSink {
+    url: String,
+    authority: String,
+    host: String,
+    path: String,
+}
+
+impl HttpSink {
+    /// Creates a new [`HttpSink`] that posts to the url received, like
+    /// "http://localhost:8080/alerts". Returns an error if it is not an http url or its
+    /// host is not a loopback address.
+    pub fn new(url: &str) -> Result<HttpSink, Error> {
+        let wrong_url = || Error::WrongAlertSinkUrl(url.to_owned());
+        let rest = url.strip_prefix(HTTP_PREFIX).ok_or_else(wrong_url)?;
+        let (host, path) = match rest.find('/') {
+            Some(idx) => rest.split_at(idx),
+            None => (rest, "/"),
+        };
+        let has_port = host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| !port.ends_with(']'));
+        let authority = match has_port {
+            true => host.to_owned(),
+            false => format!("{}:{}", host, HTTP_PORT),
+        };
+        HttpSink::resolve(&authority).ok_or_else(wrong_url)?;
+
+        Ok(HttpSink {
+            url: url.to_owned(),
+            authority,
+            host: host.to_owned(),
+            path: path.to_owned(),
+        })
+    }
+
+    // Returns the addresses of the host and port received if it has any and all of them
+    // are loopback addresses.
+    fn resolve(authority: &str) -> Option<Vec<SocketAddr>> {
+        let addresses: Vec<SocketAddr> = authority.to_socket_addrs().ok()?.collect();
+        if addresses.is_empty() || !addresses.iter().all(|address| address.ip().is_loopback()) {
+            return None;
+        }
+
+        Some(addresses)
+    }
+
+    // Connects to the first address of its host that accepts the connection.
+    fn connect(&self) -> Option<TcpStream> {
+        HttpSink::resolve(&self.authority)?
+            .iter()
+            .find_map(|address| TcpStream::connect_timeout(address, SEND_TIMEOUT).ok())
+    }
+}
+
+impl AlertSink for HttpSink {
+    fn send(&self, alert: &Alert) -> Result<(), Error> {
+        let cant_send = || Error::CantSendAlert(self.url.clone());
+        let body = serde_json::to_string(alert).map_err(|_| cant_send())?;
+        let mut stream = self.connect().ok_or_else(cant_send)?;
+        let _ = stream.set_write_timeout(Some(SEND_TIMEOUT));
+        let _ = stream.set_read_timeout(Some(SEND_TIMEOUT));
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|_| cant_send())?;
+
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok());
+        match status {
+            Some(200..=299) => Ok(()),
+            _ => Err(cant_send()),
+        }
+    }
+}
+
+/// Keeps the alerts in memory, so they can be checked after a run.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    alerts: Arc<Mutex<Vec<Alert>>>,
+}
+
+impl MemorySink {
+    /// Creates a new empty [`MemorySink`].
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    /// Returns the alerts it received, in the order they were sent.
+    pub fn get_alerts(&self) -> Vec<Alert> {
+        match self.alerts.lock() {
+            Ok(alerts) => alerts.clone(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl AlertSink for MemorySink {
+    fn send(&self, alert: &Alert) -> Result<(), Error> {
+        match self.alerts.lock() {
+            Ok(mut alerts) => {
+                alerts.push(alert.clone());
+                Ok(())
+            }
+            Err(_) => Err(Error::CantSendAlert("memory".to_owned())),
+        }
+    }
+}
+
+/// A destination of the alerts as it is written in the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSinkConfig {
+    Stdout,
+    File { path: String },
+    UnixSocket { path: String },
+    Http { url: String },
+}
+
+impl AlertSinkConfig {
+    /// Creates the sink of the configuration, returns an error if its url is wrong or
+    /// Unix sockets are not supported.
+    pub fn build(&self) -> Result<Arc<dyn AlertSink>, Error> {
+        let sink: Arc<dyn AlertSink> = match self {
+            AlertSinkConfig::Stdout => Arc::new(StdoutSink),
+            AlertSinkConfig::File { path } => Arc::new(FileSink::new(path)),
+            #[cfg(unix)]
+            AlertSinkConfig::UnixSocket { path } => Arc::new(UnixSocketSink::new(path)),
+            #[cfg(not(unix))]
+            AlertSinkConfig::UnixSocket { path } => return Err(Error::CantSendAlert(path.clone())),
+            AlertSinkConfig::Http { url } => Arc::new(HttpSink::new(url)?),
+        };
+
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::{
+        alert_sinks::{AlertSink, AlertSinkConfig, FileSink, HttpSink},
+        alerts::{Alert, AlertLevel},
+        errors::Error,
+        ingredients::Ingredient,
+    };
+
+    #[cfg(unix)]
+    use std::io::{BufRead, BufReader};
+
+    #[cfg(unix)]
+    use crate::alert_sinks::UnixSocketSink;
+
+    fn get_alert(level: AlertLevel, quantity: u32) -> Alert {
+        Alert {
+            coffee_maker_id: 1,
+            ingredient: Ingredient::Milk,
+            level,
+            quantity,
+        }
+    }
+
+    fn get_temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test01_a_file_sink_appends_every_alert_to_its_file() {
+        let path = get_temp_path("alerts.log");
+        let sink = FileSink::new(&path);
+
+        sink.send(&get_alert(AlertLevel::Warning, 25))
+            .expect("Error when sending");
+        sink.send(&get_alert(AlertLevel::Empty, 0))
+            .expect("Error when sending");
+
+        let content = std::fs::read_to_string(&path).expect("Error when reading");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            content,
+            "[ALERTER] OF [COFFEE MAKER 1]: WARNING - THE LEVEL OF THE CONTAINER OF \"milk\" IS 25\n[ALERTER] OF [COFFEE MAKER 1]: EMPTY - THE LEVEL OF THE CONTAINER OF \"milk\" IS 0\n"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test02_a_unix_socket_sink_writes_every_alert_as_a_json_line() {
+        let path = get_temp_path("alerts.sock");
+        let listener = std::os::unix::net::UnixListener::bind(&path).expect("Error when binding");
+        let reader = thread::spawn(move || {
+            let (socket, _) = listener.accept().expect("Error when accepting");
+            let mut line = String::new();
+            BufReader::new(socket)
+                .read_line(&mut line)
+                .expect("Error when reading");
+            line
+        });
+
+        UnixSocketSink::new(&path)
+            .send(&get_alert(AlertLevel::Critical, 10))
+            .expect("Error when sending");
+
+        let line = reader.join().expect("Error when joining");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            line,
+            "{\"coffee_maker_id\":1,\"ingredient\":\"milk\",\"level\":\"critical\",\"quantity\":10}\n"
+        );
+    }
+
+    #[test]
+    fn test03_an_http_sink_posts_every_alert_to_its_url() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Error when binding");
+        let port = listener.local_addr().expect("Error when binding").port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Error when accepting");
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !String::from_utf8_lossy(&request).ends_with('}') {
+                let read = stream.read(&mut buffer).expect("Error when reading");
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .expect("Error when writing");
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        HttpSink::new(&format!("http://127.0.0.1:{}/alerts", port))
+            .expect("The url is local")
+            .send(&get_alert(AlertLevel::Warning, 25))
+            .expect("Error when sending");
+
+        let request = server.join().expect("Error when joining");
+        assert!(request.starts_with("POST /alerts HTTP/1.1\r\n"));
+        assert!(request.ends_with(
+            "\r\n\r\n{\"coffee_maker_id\":1,\"ingredient\":\"milk\",\"level\":\"warning\",\"quantity\":25}"
+        ));
+    }
+
+    #[test]
+    fn test04_an_http_sink_only_posts_to_local_urls() {
+        for url in ["http://10.0.0.1:8080/alerts", "ftp://localhost/alerts"] {
+            assert_eq!(
+                HttpSink::new(url).map(|_| ()),
+                Err(Error::WrongAlertSinkUrl(url.to_string()))
+            );
+        }
+        let config: Vec<AlertSinkConfig> = serde_json::from_str(
+            "[{\"type\": \"stdout\"}, {\"type\": \"file\", \"path\": \"alerts.log\"}, {\"type\": \"http\", \"url\": \"http://localhost:9000\"}]",
+        )
+        .expect("The config is valid");
+        assert!(config.iter().all(|sink| sink.build().is_ok()));
+    }
+}
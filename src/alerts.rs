@@ -0,0 +1,237 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alert_sinks::AlertSink, containers::Containers, errors::Error, ingredients::Ingredient,
+};
+
+const WARNING_PERCENTAGE: u32 = 25;
+const CRITICAL_PERCENTAGE: u32 = 10;
+
+/// Level of the alert of a container, from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertLevel {
+    Warning,
+    Critical,
+    Empty,
+}
+
+impl fmt::Display for AlertLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertLevel::Warning => write!(f, "WARNING"),
+            AlertLevel::Critical => write!(f, "CRITICAL"),
+            AlertLevel::Empty => write!(f, "EMPTY"),
+        }
+    }
+}
+
+/// Percentages of the capacity of a container at or below which its level is alerted as
+/// a warning or as critical. An empty container is always alerted.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AlertThresholds {
+    pub warning: u32,
+    pub critical: u32,
+}
+
+impl AlertThresholds {
+    /// Returns the level of the alert of a container with the quantity and the capacity
+    /// received, or None if it doesn't have to be alerted.
+    pub fn get_level(&self, quantity: u32, capacity: u32) -> Option<AlertLevel> {
+        let filled = quantity as u64 * 100;
+        if capacity == 0 {
+            None
+        } else if quantity == 0 {
+            Some(AlertLevel::Empty)
+        } else if filled <= self.critical as u64 * capacity as u64 {
+            Some(AlertLevel::Critical)
+        } else if filled <= self.warning as u64 * capacity as u64 {
+            Some(AlertLevel::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        AlertThresholds {
+            warning: WARNING_PERCENTAGE,
+            critical: CRITICAL_PERCENTAGE,
+        }
+    }
+}
+
+/// An alert of the level of the container of an ingredient of a coffee maker.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub coffee_maker_id: u32,
+    pub ingredient: Ingredient,
+    pub level: AlertLevel,
+    pub quantity: u32,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[ALERTER] OF [COFFEE MAKER {:?}]: {} - THE LEVEL OF THE CONTAINER OF {:?} IS {:?}",
+            self.coffee_maker_id,
+            self.level,
+            self.ingredient.name(),
+            self.quantity
+        )
+    }
+}
+
+/// Decides which levels of the containers of a coffee maker have to be alerted. Every
+/// level is alerted once when a container drops to it, and again only after the container
+/// was refilled above it. The alerts are sent to its sinks.
+#[derive(Clone)]
+pub struct Alerter {
+    pub coffee_maker_id: u32,
+    thresholds: AlertThresholds,
+    ingredients: Vec<Ingredient>,
+    levels: HashMap<Ingredient, AlertLevel>,
+    sinks: Vec<Arc<dyn AlertSink>>,
+}
+
+impl Alerter {
+    /// Creates a new [`Alerter`] of the containers of the ingredients received, in the
+    /// order of their alert flags, where no level was alerted yet.
+    pub fn new(
+        coffee_maker_id: u32,
+        thresholds: AlertThresholds,
+        ingredients: Vec<Ingredient>,
+    ) -> Alerter {
+        Alerter {
+            coffee_maker_id,
+            thresholds,
+            ingredients,
+            levels: HashMap::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Returns the alerter with the sinks received, where its alerts are sent.
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn AlertSink>>) -> Alerter {
+        self.sinks = sinks;
+        self
+    }
+
+    /// Sends the alert to every one of its sinks. A sink that fails doesn't stop the
+    /// others, its error is only shown.
+    pub fn send(&self, alert: &Alert) {
+        for sink in &self.sinks {
+            if let Err(err) = sink.send(alert) {
+                println!(
+                    "[ALERTER] OF [COFFEE MAKER {:?}]: {:?} WHEN SENDING {}",
+                    self.coffee_maker_id, err, alert.level
+                );
+            }
+        }
+    }
+
+    /// Returns the ingredients of its containers, in the order of their alert flags.
+    pub fn get_ingredients(&self) -> &[Ingredient] {
+        &self.ingredients
+    }
+
+    /// Takes the current level of every container as already alerted, so only the levels
+    /// they drop to from now on are alerted.
+    pub fn arm(&mut self, containers: &Containers) -> Result<(), Error> {
+        self.levels.clear();
+        for ingredient in &self.ingredients {
+            let quantity = containers.get_quantity_of(ingredient)?;
+            let capacity = containers.get_capacity_of(ingredient)?;
+            if let Some(level) = self.thresholds.get_level(quantity, capacity) {
+                self.levels.insert(ingredient.clone(), level);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the level of the container of the ingredient with the quantity and the
+    /// capacity received. Returns the alert if it dropped to a level that was not alerted,
+    /// or None if not. A container that was refilled can be alerted again.
+    pub fn check(
+        &mut self,
+        ingredient: &Ingredient,
+        quantity: u32,
+        capacity: u32,
+    ) -> Option<Alert> {
+        let level = self.thresholds.get_level(quantity, capacity);
+        let alerted = self.levels.get(ingredient).copied();
+        if level == alerted {
+            return None;
+        }
+        match level {
+            Some(level) => self.levels.insert(ingredient.clone(), level),
+            None => self.levels.remove(ingredient),
+        };
+        if level < alerted {
+            return None;
+        }
+
+        level.map(|level| Alert {
+            coffee_maker_id: self.coffee_maker_id,
+            ingredient: ingredient.clone(),
+            level,
+            quantity,
+        })
+    }
+
+    /// Checks the level of the container of the ingredient received.
+    pub fn check_container(
+        &mut self,
+        containers: &Containers,
+        ingredient: &Ingredient,
+    ) -> Result<Option<Alert>, Error> {
+        let quantity = containers.get_quantity_of(ingredient)?;
+        let capacity = containers.get_capacity_of(ingredient)?;
+
+        Ok(self.check(ingredient, quantity, capacity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::{AlertLevel, AlertThresholds, Alerter},
+        ingredients::Ingredient,
+    };
+
+    #[test]
+    fn test01_get_the_level_of_a_container_from_its_thresholds() {
+        let thresholds = AlertThresholds::default();
+
+        assert_eq!(thresholds.get_level(26, 100), None);
+        assert_eq!(thresholds.get_level(25, 100), Some(AlertLevel::Warning));
+        assert_eq!(thresholds.get_level(10, 100), Some(AlertLevel::Critical));
+        assert_eq!(thresholds.get_level(0, 100), Some(AlertLevel::Empty));
+        assert_eq!(thresholds.get_level(0, 0), None);
+    }
+
+    #[test]
+    fn test02_every_level_is_alerted_once_per_crossing_and_again_after_a_refill() {
+        let mut alerter = Alerter::new(0, AlertThresholds::default(), vec![Ingredient::Milk]);
+        let mut check = |quantity| {
+            alerter
+                .check(&Ingredient::Milk, quantity, 200)
+                .map(|alert| alert.level)
+        };
+
+        assert_eq!(check(60), None);
+        assert_eq!(check(45), Some(AlertLevel::Warning));
+        assert_eq!(check(30), None);
+        assert_eq!(check(0), Some(AlertLevel::Empty));
+        assert_eq!(check(40), None);
+        assert_eq!(check(15), Some(AlertLevel::Critical));
+        assert_eq!(check(150), None);
+        assert_eq!(check(50), Some(AlertLevel::Warning));
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use crate::{container::SharedContainer, errors::Error, ingredients::Ingredient};
+
+/// A container whose quantity is kept in an atomic, so dispensers and refills don't wait
+/// for each other: a dispense decrements it with compare-and-swap and a refill increments
+/// it with fetch-add, or with compare-and-swap if it has a capacity. The quantity is kept
+/// in 64 bits so adding to a container without limit never overflows. It doesn't keep an
+/// audit log nor the time its quantity was added.
+#[derive(Debug)]
+pub struct AtomicContainer {
+    pub ingredient: Ingredient,
+    pub capacity: u32,
+    quantity: AtomicU64,
+    replenishments: AtomicU32,
+    replenished: AtomicU32,
+}
+
+impl AtomicContainer {
+    /// Creates a new [`AtomicContainer`] without a limit of quantity.
+    pub fn new(ingredient: Ingredient, value: u32) -> AtomicContainer {
+        AtomicContainer {
+            ingredient,
+            capacity: u32::MAX,
+            quantity: AtomicU64::new(value as u64),
+            replenishments: AtomicU32::new(0),
+            replenished: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns the same [`AtomicContainer`] with the capacity received, discarding the
+    /// quantity that doesn't fit in it.
+    pub fn with_capacity(mut self, capacity_value: u32) -> AtomicContainer {
+        self.capacity = capacity_value;
+        let quantity = self.quantity.get_mut();
+        *quantity = (*quantity).min(capacity_value as u64);
+        self
+    }
+
+    /// Returns how many refills added some quantity.
+    pub fn get_replenishments(&self) -> u32 {
+        self.replenishments.load(Ordering::Relaxed)
+    }
+
+    /// Returns the quantity that all the refills added.
+    pub fn get_replenished(&self) -> u32 {
+        self.replenished.load(Ordering::Relaxed)
+    }
+
+    /// Returns the quantity that can be added to the container until it is full.
+    pub fn get_free_space(&self) -> u32 {
+        let quantity = self.quantity.load(Ordering::Acquire);
+        u32::try_from((self.capacity as u64).saturating_sub(quantity)).unwrap_or(u32::MAX)
+    }
+
+    /// Returns the percentage of its capacity that is filled.
+    pub fn get_fill_percentage(&self) -> u32 {
+        match self.capacity {
+            0 => 0,
+            capacity => (self.quantity.load(Ordering::Acquire) * 100 / capacity as u64) as u32,
+        }
+    }
+
+    // Adds the quantity received to the container if it has no limit, or only the quantity
+    // that fits in it if it has one. Returns the quantity that was added.
+    fn add(&self, value: u32) -> u32 {
+        if self.capacity == u32::MAX {
+            self.quantity.fetch_add(value as u64, Ordering::AcqRel);
+            return value;
+        }
+
+        let mut current = self.quantity.load(Ordering::Acquire);
+        loop {
+            let added = (value as u64).min((self.capacity as u64).saturating_sub(current));
+            match self.quantity.compare_exchange_weak(
+                current,
+                current + added,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return added as u32,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Decrements its quantity if it has enough, returns an error if not. Returns the
+    /// quantity it had before.
+    pub fn take(&self, value: u32) -> Result<u32, Error> {
+        let mut current = self.quantity.load(Ordering::Acquire);
+        loop {
+            if current < value as u64 {
+                return Err(Error::NotEnoughIngredient);
+            }
+            match self.quantity.compare_exchange_weak(
+                current,
+                current - value as u64,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Ok(u32::try_from(current).unwrap_or(u32::MAX)),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    // Prints a dispense like the ones of the containers behind a lock.
+    pub(crate) fn print_dispense(
+        &self,
+        value: u32,
+        before: u32,
+        dispenser_id: Option<u32>,
+        coffee_maker_id: u32,
+    ) {
+        println!(
+            "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: GETTING {:?} OF {:?} FROM {:?}",
+            dispenser_id,
+            coffee_maker_id,
+            value,
+            self.ingredient.name(),
+            before
+        );
+    }
+
+    /// Gives back a quantity that was dispensed, up to its capacity: the quantity whose
+    /// place was filled by a refill in the meantime is discarded. Returns the quantity that
+    /// was given back.
+    pub fn give_back(&self, value: u32) -> u32 {
+        self.add(value)
+    }
+
+    /// Increments its quantity with the stock brought by an operator, up to its capacity,
+    /// without counting a refill. Returns the quantity that was added.
+    pub fn restock(&self, value: u32) -> u32 {
+        self.add(value)
+    }
+
+    /// Sets its quantity, up to its capacity, and how many refills added how much quantity.
+    pub fn restore(&self, value: u32, replenishments: u32, replenished: u32) {
+        self.quantity
+            .store(value.min(self.capacity) as u64, Ordering::Release);
+        self.replenishments.store(replenishments, Ordering::Relaxed);
+        self.replenished.store(replenished, Ordering::Relaxed);
+    }
+}
+
+impl SharedContainer for AtomicContainer {
+    fn dispense(&self, value: u32) -> Result<(), Error> {
+        self.take(value).map(|_| ())
+    }
+
+    fn refill(&self, value: u32) -> Result<u32, Error> {
+        let added = self.add(value);
+        if added > 0 {
+            self.replenishments.fetch_add(1, Ordering::Relaxed);
+            self.replenished.fetch_add(added, Ordering::Relaxed);
+        }
+
+        Ok(added)
+    }
+
+    fn get_quantity(&self) -> Result<u32, Error> {
+        let quantity = self.quantity.load(Ordering::Acquire);
+        Ok(u32::try_from(quantity).unwrap_or(u32::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        atomic_container::AtomicContainer, container::SharedContainer, errors::Error,
+        ingredients::Ingredient,
+    };
+
+    #[test]
+    fn test01_dispense_and_refill_up_to_its_capacity() {
+        let container = AtomicContainer::new(Ingredient::Milk, 50).with_capacity(40);
+
+        assert_eq!(container.get_quantity(), Ok(40));
+        assert_eq!(container.dispense(15), Ok(()));
+        assert_eq!(container.dispense(30), Err(Error::NotEnoughIngredient));
+        assert_eq!(container.refill(20), Ok(15));
+        assert_eq!(container.refill(20), Ok(0));
+        assert_eq!(container.get_replenishments(), 1);
+        assert_eq!(container.get_replenished(), 15);
+        assert_eq!(container.get_fill_percentage(), 100);
+        assert_eq!(container.restock(10), 0);
+    }
+
+    #[test]
+    fn test02_many_dispensers_never_take_more_than_there_is() {
+        let container = Arc::new(AtomicContainer::new(Ingredient::Coffee, 1000));
+        let mut handles = Vec::new();
+        for _ in 0..8 {
+            let container = container.clone();
+            handles.push(thread::spawn(move || {
+                (0..200).filter(|_| container.dispense(1).is_ok()).count()
+            }));
+        }
+
+        let dispensed: usize = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Error when joining"))
+            .sum();
+        assert_eq!(dispensed, 1000);
+        assert_eq!(container.get_quantity(), Ok(0));
+    }
+
+    #[test]
+    fn test03_give_back_after_a_restock_never_goes_above_its_capacity() {
+        let container = AtomicContainer::new(Ingredient::Cocoa, 20).with_capacity(20);
+
+        assert_eq!(container.take(10), Ok(20));
+        assert_eq!(container.restock(15), 10);
+        assert_eq!(container.give_back(10), 0);
+        assert_eq!(container.get_quantity(), Ok(20));
+        assert_eq!(container.get_fill_percentage(), 100);
+        assert_eq!(container.take(5), Ok(20));
+        assert_eq!(container.give_back(10), 5);
+        assert_eq!(container.get_quantity(), Ok(20));
+    }
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+
+use crate::{coffee_maker::CoffeeMaker, errors::Error, ingredients::Ingredient};
+
+/// What was done with a container.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    Dispense,
+    Replenish,
+    Restock,
+    Discard,
+}
+
+/// Who operated the container: a dispenser of its coffee maker, its ingredient handler
+/// or an operator.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActor {
+    Dispenser(u32),
+    IngredientHandler,
+    Operator,
+}
+
+/// A dispense, a replenishment, a restock or a discard of the expired quantity of a
+/// container: when it happened, who did it, how much was taken or added and the level of
+/// the container before and after it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    pub coffee_maker_id: u32,
+    pub ingredient: Ingredient,
+    pub actor: AuditActor,
+    pub operation: AuditOperation,
+    pub amount: u32,
+    pub before: u32,
+    pub after: u32,
+}
+
+/// Append-only log of the operations of a container, or of many containers when they
+/// are exported together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Creates a new empty [`AuditLog`].
+    pub fn new() -> AuditLog {
+        AuditLog::default()
+    }
+
+    /// Adds an entry at the end of the log.
+    pub fn record(&mut self, entry: AuditEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Returns the entries of the log in the order they were recorded.
+    pub fn get_entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Returns a new [`AuditLog`] with the entries of all the logs received, sorted by
+    /// their timestamp. The entries with the same timestamp keep their order.
+    pub fn merge(logs: &[AuditLog]) -> AuditLog {
+        let mut entries: Vec<AuditEntry> = logs
+            .iter()
+            .flat_map(|log| log.entries.iter().cloned())
+            .collect();
+        entries.sort_by_key(|entry| entry.timestamp_ms);
+
+        AuditLog { entries }
+    }
+
+    /// Returns the log of all the containers of all the coffee makers received.
+    pub fn from_coffee_makers(coffee_makers: &[CoffeeMaker]) -> Result<AuditLog, Error> {
+        let mut logs = Vec::new();
+        for coffee_maker in coffee_makers {
+            logs.push(coffee_maker.containers.get_audit_log()?);
+        }
+
+        Ok(AuditLog::merge(&logs))
+    }
+
+    /// Converts the log to json lines, one entry per line.
+    pub fn to_json_lines(&self) -> Result<String, Error> {
+        let mut lines = String::new();
+        for entry in &self.entries {
+            match serde_json::to_string(entry) {
+                Ok(line) => {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+                Err(_) => return Err(Error::CantWriteAuditLog),
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Writes the log as json lines to the path received.
+    pub fn write_to(&self, path: &str) -> Result<(), Error> {
+        match std::fs::write(path, self.to_json_lines()?) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::CantWriteAuditLog),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        audit::{AuditActor, AuditEntry, AuditLog, AuditOperation},
+        ingredients::Ingredient,
+    };
+
+    fn get_entry(timestamp_ms: u64, actor: AuditActor) -> AuditEntry {
+        AuditEntry {
+            timestamp_ms,
+            coffee_maker_id: 0,
+            ingredient: Ingredient::Milk,
+            actor,
+            operation: AuditOperation::Dispense,
+            amount: 10,
+            before: 50,
+            after: 40,
+        }
+    }
+
+    #[test]
+    fn test01_merge_logs_sorts_their_entries_by_timestamp() {
+        let mut first = AuditLog::new();
+        first.record(get_entry(0, AuditActor::Dispenser(0)));
+        first.record(get_entry(2000, AuditActor::Dispenser(1)));
+        let mut second = AuditLog::new();
+        second.record(get_entry(1000, AuditActor::IngredientHandler));
+
+        let timestamps: Vec<u64> = AuditLog::merge(&[first, second])
+            .get_entries()
+            .iter()
+            .map(|entry| entry.timestamp_ms)
+            .collect();
+
+        assert_eq!(timestamps, vec![0, 1000, 2000]);
+    }
+
+    #[test]
+    fn test02_convert_a_log_to_json_lines() {
+        let mut log = AuditLog::new();
+        log.record(get_entry(0, AuditActor::Dispenser(2)));
+        log.record(get_entry(1000, AuditActor::IngredientHandler));
+
+        let json = log.to_json_lines().expect("Error when converting to json");
+        let lines: Vec<&str> = json.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "{\"timestamp_ms\":0,\"coffee_maker_id\":0,\"ingredient\":\"milk\",\"actor\":{\"dispenser\":2},\"operation\":\"dispense\",\"amount\":10,\"before\":50,\"after\":40}"
+        );
+        assert!(lines[1].contains("\"actor\":\"ingredient_handler\""));
+    }
+}
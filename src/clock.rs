@@ -0,0 +1,165 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+static NEXT_VIRTUAL_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The logical time, in milliseconds, of the current thread in every virtual clock.
+    static VIRTUAL_TIMES: RefCell<HashMap<u64, u64>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClockKind {
+    Real { start: Instant, speed_up: f64 },
+    Virtual { id: u64 },
+}
+
+/// Source of the time of a simulation, shared between all its threads.
+/// A real clock sleeps for real, optionally faster than the logical time by a speed-up
+/// factor. A virtual clock never sleeps: every thread keeps its own logical time, that
+/// advances instantly when it sleeps and catches up with the time of the events it waits
+/// for, so the timings of the stats and the reports are the same as with a real clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    kind: ClockKind,
+}
+
+impl Clock {
+    /// Creates a new real [`Clock`] whose time is measured from now.
+    pub fn real() -> Clock {
+        Clock::with_speed_up(1.0)
+    }
+
+    /// Creates a new real [`Clock`] whose time is measured from now and runs the speed-up
+    /// factor received times faster than the real time.
+    pub fn with_speed_up(speed_up: f64) -> Clock {
+        let speed_up = if speed_up.is_finite() && speed_up > 0.0 {
+            speed_up
+        } else {
+            1.0
+        };
+
+        Clock {
+            kind: ClockKind::Real {
+                start: Instant::now(),
+                speed_up,
+            },
+        }
+    }
+
+    /// Creates a new virtual [`Clock`] whose time starts at zero in every thread.
+    pub fn simulated() -> Clock {
+        Clock {
+            kind: ClockKind::Virtual {
+                id: NEXT_VIRTUAL_ID.fetch_add(1, Ordering::Relaxed),
+            },
+        }
+    }
+
+    /// Returns true if the clock never sleeps.
+    pub fn is_virtual(&self) -> bool {
+        matches!(self.kind, ClockKind::Virtual { .. })
+    }
+
+    /// Returns the logical milliseconds elapsed since the start of the clock.
+    pub fn now_ms(&self) -> u64 {
+        match &self.kind {
+            ClockKind::Real { start, speed_up } => {
+                (start.elapsed().as_secs_f64() * 1000.0 * speed_up) as u64
+            }
+            ClockKind::Virtual { id } => {
+                VIRTUAL_TIMES.with(|times| times.borrow().get(id).copied().unwrap_or_default())
+            }
+        }
+    }
+
+    /// Returns the real duration of the logical duration received: shorter by the
+    /// speed-up factor in a real clock and none in a virtual one, that never sleeps.
+    pub fn get_real_duration(&self, duration: Duration) -> Duration {
+        match &self.kind {
+            ClockKind::Real { speed_up, .. } => duration.div_f64(*speed_up),
+            ClockKind::Virtual { .. } => Duration::ZERO,
+        }
+    }
+
+    /// Waits the logical duration received.
+    pub fn sleep(&self, duration: Duration) {
+        match &self.kind {
+            ClockKind::Real { .. } => thread::sleep(self.get_real_duration(duration)),
+            ClockKind::Virtual { .. } => {
+                self.advance_to_ms(self.now_ms() + duration.as_millis() as u64)
+            }
+        }
+    }
+
+    /// Waits until the logical time received, if it didn't pass yet.
+    /// A virtual clock moves the time of the current thread forward to it, so it is also
+    /// used to catch up with the time of an event that happened in another thread.
+    pub fn advance_to_ms(&self, time_ms: u64) {
+        let now_ms = self.now_ms();
+        if time_ms <= now_ms {
+            return;
+        }
+        match &self.kind {
+            ClockKind::Real { .. } => self.sleep(Duration::from_millis(time_ms - now_ms)),
+            ClockKind::Virtual { id } => VIRTUAL_TIMES.with(|times| {
+                times.borrow_mut().insert(*id, time_ms);
+            }),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::real()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::clock::Clock;
+
+    #[test]
+    fn test01_a_virtual_clock_advances_without_sleeping() {
+        let clock = Clock::simulated();
+        let real_clock = Clock::real();
+        clock.sleep(Duration::from_secs(3600));
+
+        assert_eq!(clock.now_ms(), 3_600_000);
+        assert!(real_clock.now_ms() < 1000);
+    }
+
+    #[test]
+    fn test02_every_thread_has_its_own_time_in_a_virtual_clock() {
+        let clock = Clock::simulated();
+        clock.sleep(Duration::from_millis(500));
+        let other_clock = clock.clone();
+        let other_time = thread::spawn(move || {
+            other_clock.sleep(Duration::from_millis(200));
+            other_clock.advance_to_ms(100);
+            other_clock.now_ms()
+        })
+        .join()
+        .expect("Error when joining");
+
+        assert_eq!(other_time, 200);
+        assert_eq!(clock.now_ms(), 500);
+    }
+
+    #[test]
+    fn test03_a_real_clock_with_speed_up_runs_faster() {
+        let clock = Clock::with_speed_up(100.0);
+        let real_clock = Clock::real();
+        clock.sleep(Duration::from_secs(5));
+
+        assert!(clock.now_ms() >= 5000);
+        assert!(real_clock.now_ms() < 1000);
+    }
+}
@@ -1,43 +1,157 @@
+use crate::alert_sinks::AlertSink;
+use crate::alerts::{Alert, Alerter};
+use crate::clock::Clock;
+use crate::config::CoffeeMakerConfig;
 use crate::containers::Containers;
+use crate::errors::Error;
 use crate::ingredient_handler::IHandler;
-use crate::orders_handler::order_handler::process_order;
+use crate::ingredients::{Ingredient, IngredientRegistry};
+use crate::orders_handler::order_handler::{notify_to_alert, notify_to_replenish, process_order};
+use crate::orders_queue::OrdersQueue;
+use crate::report::Report;
+use crate::snapshot::CoffeeMakerSnapshot;
 use crate::stats_presenter::presenter::show_alert_of_capacity;
-use crate::{errors::Error, orders::Order};
-use std::collections::HashMap;
-use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread::{self, JoinHandle};
-
-const DISPENSERS: u32 = 3;
-const COFFEE: &str = "coffee";
-const FOAM: &str = "foam";
-const HOT_WATER: &str = "hot_water";
-const INGREDIENTS_TO_REPLENISH: [&str; 3] = [COFFEE, FOAM, HOT_WATER];
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct CoffeeMaker {
     pub id: u32,
     pub containers: Containers,
     pub handler: IHandler,
+    pub config: CoffeeMakerConfig,
+    pub registry: IngredientRegistry,
+    pub pending_alerts: Vec<Ingredient>,
+    has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
+    alert_sinks: Vec<Arc<dyn AlertSink>>,
 }
 
 impl CoffeeMaker {
-    /// Creates a new [`CoffeeMaker`].
-    pub fn new(id_value: u32, initial_quantity: u32, replenish_value: u32) -> CoffeeMaker {
-        let c = Containers::new(initial_quantity);
+    /// Creates a new [`CoffeeMaker`] from its configuration.
+    pub fn new(config: CoffeeMakerConfig) -> CoffeeMaker {
+        CoffeeMaker::with_clock(config, Clock::real())
+    }
+
+    /// Creates a new [`CoffeeMaker`] from its configuration whose containers take their
+    /// time from the clock received.
+    pub fn with_clock(config: CoffeeMakerConfig, clock: Clock) -> CoffeeMaker {
+        let c = Containers::with_clock(&config, clock);
+        let alert_flags = vec![false; config.get_ingredients().len()];
         CoffeeMaker {
-            id: id_value,
+            id: config.id,
             containers: c.clone(),
-            handler: IHandler::new(c, id_value, replenish_value),
+            handler: IHandler::new(c, &config),
+            registry: config.get_registry(),
+            config,
+            pending_alerts: Vec::new(),
+            has_to_alert: Arc::new((Mutex::new(alert_flags), Condvar::new())),
+            alert_sinks: Vec::new(),
+        }
+    }
+
+    /// Adds a sink where the alerts of its containers are sent, besides the ones of its
+    /// configuration.
+    pub fn add_alert_sink(&mut self, sink: Arc<dyn AlertSink>) {
+        self.alert_sinks.push(sink);
+    }
+
+    /// Returns the current state of its containers and its pending alerts.
+    pub fn get_snapshot(&self) -> Result<CoffeeMakerSnapshot, Error> {
+        Ok(CoffeeMakerSnapshot {
+            id: self.id,
+            containers: self.containers.get_snapshot()?,
+            pending_alerts: self.handler.get_pending_alerts()?,
+        })
+    }
+
+    /// Restores the state of its containers and its pending alerts from the snapshot.
+    /// The restored quantities become the initial quantities of its configuration, keeping
+    /// the capacity of every container.
+    pub fn restore(&mut self, snapshot: &CoffeeMakerSnapshot) -> Result<(), Error> {
+        self.containers.restore(&snapshot.containers)?;
+        for (ingredient, container) in &snapshot.containers {
+            let capacity = self.config.get_capacity(ingredient);
+            self.config
+                .capacities
+                .insert(ingredient.name().to_owned(), capacity);
+            self.config.containers.insert(
+                ingredient.name().to_owned(),
+                container.quantity.min(capacity),
+            );
         }
+        self.pending_alerts = snapshot.pending_alerts.clone();
+
+        Ok(())
+    }
+
+    /// Adds the stock brought by an operator to the container of the ingredient, even while
+    /// it is making orders, up to the capacity of the container. The dispensers that wait
+    /// for a restock try again and the alert of the ingredient is checked, so it is re-armed.
+    /// Returns the quantity that was added.
+    pub fn restock(&self, ingredient: &Ingredient, amount: u32) -> Result<u32, Error> {
+        let added = self.containers.restock(ingredient, amount, self.id)?;
+        self.handler.notify_to_alert_ingredients(
+            self.has_to_alert.clone(),
+            std::slice::from_ref(ingredient),
+        );
+
+        Ok(added)
     }
 
-    /// Calls to the ingredient handler to perform the replenishing of ingredients.
+    /// Returns how long its dispensers wait for an operator to restock an ingredient,
+    /// or None if they don't wait.
+    pub fn get_restock_wait(&self) -> Option<Duration> {
+        match self.config.restock_wait_ms {
+            0 => None,
+            wait_ms => Some(Duration::from_millis(wait_ms)),
+        }
+    }
+
+    /// Shows again the alerts that were pending when its state was restored, if their
+    /// containers are still at an alert level.
+    fn show_pending_alerts(&self, alerter: &Alerter) -> Result<(), Error> {
+        for ingredient in &self.pending_alerts {
+            let quantity = self.containers.get_quantity_of(ingredient)?;
+            let capacity = self.containers.get_capacity_of(ingredient)?;
+            if let Some(level) = self.config.alert_levels.get_level(quantity, capacity) {
+                let alert = Alert {
+                    coffee_maker_id: self.id,
+                    ingredient: ingredient.clone(),
+                    level,
+                    quantity,
+                };
+                alerter.send(&alert);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the alerter of its containers, which sends their alerts to the sinks of its
+    /// configuration and to the ones added.
+    fn get_alerter(&self) -> Result<Alerter, Error> {
+        let mut sinks = self.config.get_alert_sinks()?;
+        sinks.extend(self.alert_sinks.iter().cloned());
+
+        Ok(Alerter::new(
+            self.id,
+            self.config.alert_levels,
+            self.config.get_ingredients(),
+        )
+        .with_sinks(sinks))
+    }
+
+    /// Calls to the ingredient handler to perform the replenishing of ingredients, one
+    /// thread per replenish rule, until all the dispensers finished.
     fn handle_replenish(
         self,
-        orders: Arc<RwLock<Vec<Order>>>,
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
         handler_is_awake: Arc<(Mutex<Vec<bool>>, Condvar)>,
         has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
+        alerter: Alerter,
+        dispensers_finished: Arc<AtomicBool>,
     ) -> Result<(), Error> {
         println!(
             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: STARTING",
@@ -45,16 +159,17 @@ impl CoffeeMaker {
         );
 
         let mut handlers: Vec<JoinHandle<Result<(), Error>>> = Vec::new();
-        for (i, ingredient) in INGREDIENTS_TO_REPLENISH.iter().enumerate() {
+        for (i, rule) in self.handler.get_rules().iter().enumerate() {
+            let ingredient = rule.target.clone();
             let mut coffee_maker = self.clone();
             let has_to_replenish = has_to_replenish.clone();
             let handler_is_awake = handler_is_awake.clone();
             let has_to_alert = has_to_alert.clone();
 
-            let orders = orders.clone();
+            let dispensers_finished = dispensers_finished.clone();
             let handle: JoinHandle<Result<(), Error>> = thread::spawn(move || loop {
                 match coffee_maker.handler.do_replenish(
-                    &ingredient.to_string(),
+                    &ingredient,
                     has_to_replenish.clone(),
                     handler_is_awake.clone(),
                     i,
@@ -75,17 +190,14 @@ impl CoffeeMaker {
                     }
                 }
 
-                if let Ok(orders) = orders.read() {
-                    if orders.is_empty() {
-                        return Err(Error::NoMoreOrders);
-                    }
+                if dispensers_finished.load(Ordering::SeqCst) {
+                    return Err(Error::NoMoreOrders);
                 }
             });
             handlers.push(handle)
         }
 
-        let values = self.clone().get_values();
-        show_alert_of_capacity(orders, self.containers, self.id, has_to_alert, values)?;
+        show_alert_of_capacity(self.containers, has_to_alert, alerter, dispensers_finished)?;
 
         for handle in handlers {
             match handle.join() {
@@ -103,33 +215,30 @@ impl CoffeeMaker {
         Ok(())
     }
 
-    fn get_values(self) -> HashMap<i32, String> {
-        let mut values = HashMap::new();
-        values.insert(0, "grain_coffee".to_string());
-        values.insert(1, "milk".to_string());
-        values.insert(2, "cocoa".to_string());
-
-        values
-    }
-
     /// Makes its dispensers to start making orders.
+    /// The outcome of every order is added to the report received.
     pub fn start(
         self,
-        orders: &Arc<RwLock<Vec<Order>>>,
+        orders: &Arc<OrdersQueue>,
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
+        report: Report,
     ) -> Result<(), Error> {
+        let mut alerter = self.get_alerter()?;
+        self.show_pending_alerts(&alerter)?;
+        alerter.arm(&self.containers)?;
+        let rules = self.handler.get_rules().len();
         let has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)> =
-            Arc::new((Mutex::new(vec![false, false, false]), Condvar::new()));
+            Arc::new((Mutex::new(vec![false; rules]), Condvar::new()));
         let handler_is_awake: Arc<(Mutex<Vec<bool>>, Condvar)> =
-            Arc::new((Mutex::new(vec![false, false, false]), Condvar::new()));
-        let has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)> =
-            Arc::new((Mutex::new(vec![false, false, false]), Condvar::new()));
+            Arc::new((Mutex::new(vec![false; rules]), Condvar::new()));
+        let has_to_alert = self.has_to_alert.clone();
 
         let mut dispensers: Vec<JoinHandle<Result<(), Error>>> = Vec::new();
-        for i in 0..DISPENSERS {
+        for i in 0..self.config.dispensers {
             let orders = Arc::clone(orders);
             let orders_processed = orders_processed.clone();
             let coffee_machine = self.clone();
+            let report = report.clone();
 
             let has_to_replenish = has_to_replenish.clone();
             let handler_is_awake = handler_is_awake.clone();
@@ -157,6 +266,7 @@ impl CoffeeMaker {
                             orders_processed,
                             has_to_replenish,
                             has_to_alert,
+                            report,
                         )?;
                     }
                 }
@@ -167,12 +277,19 @@ impl CoffeeMaker {
             dispensers.push(handle);
         }
 
-        self.clone().handle_replenish(
-            Arc::clone(orders),
-            has_to_replenish,
-            handler_is_awake,
-            has_to_alert,
-        )?;
+        let dispensers_finished = Arc::new(AtomicBool::new(false));
+        let coffee_maker = self.clone();
+        let replenish_flags = (has_to_replenish.clone(), has_to_alert.clone());
+        let replenish_finished = dispensers_finished.clone();
+        let handler = thread::spawn(move || {
+            coffee_maker.handle_replenish(
+                replenish_flags.0,
+                handler_is_awake,
+                replenish_flags.1,
+                alerter,
+                replenish_finished,
+            )
+        });
 
         for handle in dispensers {
             match handle.join() {
@@ -184,23 +301,47 @@ impl CoffeeMaker {
             }
         }
 
-        Ok(())
+        dispensers_finished.store(true, Ordering::SeqCst);
+        notify_to_replenish(has_to_replenish, true);
+        notify_to_alert(has_to_alert);
+        match handler.join() {
+            Ok(result) => result,
+            Err(err) => {
+                println!(
+                    "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {:?} WHEN JOINING",
+                    self.id, err
+                );
+                Ok(())
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Condvar, Mutex, RwLock};
-
+    use std::collections::HashMap;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::thread;
+
+    use crate::alert_sinks::MemorySink;
+    use crate::alerts::{Alert, AlertLevel};
+    use crate::clock::Clock;
+    use crate::config::{CoffeeMakerConfig, ReplenishRuleConfig};
+    use crate::container::ContainerBackend;
     use crate::errors::Error;
+    use crate::feeder::feed_orders;
+    use crate::ingredients::Ingredient;
     use crate::orders_handler::order_handler::process_order;
+    use crate::orders_queue::OrdersQueue;
+    use crate::report::{OrderStatus, Report};
     use crate::{coffee_maker::CoffeeMaker, orders::Order};
 
     #[test]
     fn test01_get_an_order_when_there_are_no_orders() {
-        let coffee_maker = CoffeeMaker::new(0, 100, 50);
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
         let orders_list = Vec::new();
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
         let has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)> =
             Arc::new((Mutex::new(vec![false, false, false]), Condvar::new()));
@@ -214,6 +355,7 @@ mod tests {
             orders_processed,
             has_to_replenish,
             has_to_alert,
+            Report::new(),
         )
         .expect_err("There are no more orders");
         let err_expected = Error::NoMoreOrders;
@@ -226,30 +368,31 @@ mod tests {
         let mut orders_list = Vec::new();
         let order = Order::new(10, 10, 5, 5);
         orders_list.push(order);
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
-        let coffee_maker = CoffeeMaker::new(0, 100, 50);
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
         coffee_maker
             .clone()
-            .start(&orders, orders_processed)
+            .start(&orders, orders_processed, Report::new())
             .expect("Error when starting");
 
         let coffee_got = coffee_maker
             .containers
-            .get_quantity_of(&"coffee".to_string())
+            .get_quantity_of(&Ingredient::Coffee)
             .expect("Error when locking coffee container");
         let foam_got = coffee_maker
             .containers
-            .get_quantity_of(&"foam".to_string())
+            .get_quantity_of(&Ingredient::Foam)
             .expect("Error when locking foam container");
         let hot_water_got = coffee_maker
             .containers
-            .get_quantity_of(&"hot_water".to_string())
+            .get_quantity_of(&Ingredient::HotWater)
             .expect("Error when locking hot water container");
         let cocoa_got = coffee_maker
             .containers
-            .get_quantity_of(&"cocoa".to_string())
+            .get_quantity_of(&Ingredient::Cocoa)
             .expect("Error when locking cocoa container");
 
         assert_eq!(coffee_got, 90);
@@ -265,30 +408,31 @@ mod tests {
         for _ in 0..5 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
-        let coffee_maker = CoffeeMaker::new(0, 100, 50);
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
         coffee_maker
             .clone()
-            .start(&orders, orders_processed)
+            .start(&orders, orders_processed, Report::new())
             .expect("Error when starting");
 
         let coffee_got = coffee_maker
             .containers
-            .get_quantity_of(&"coffee".to_string())
+            .get_quantity_of(&Ingredient::Coffee)
             .expect("Error when locking coffee container");
         let foam_got = coffee_maker
             .containers
-            .get_quantity_of(&"foam".to_string())
+            .get_quantity_of(&Ingredient::Foam)
             .expect("Error when locking foam container");
         let hot_water_got = coffee_maker
             .containers
-            .get_quantity_of(&"hot_water".to_string())
+            .get_quantity_of(&Ingredient::HotWater)
             .expect("Error when locking hot water container");
         let cocoa_got = coffee_maker
             .containers
-            .get_quantity_of(&"cocoa".to_string())
+            .get_quantity_of(&Ingredient::Cocoa)
             .expect("Error when locking cocoa container");
 
         assert_eq!(coffee_got, 50);
@@ -304,18 +448,19 @@ mod tests {
         for _ in 0..12 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
-        let coffee_maker = CoffeeMaker::new(0, 100, 50);
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
         coffee_maker
             .clone()
-            .start(&orders, orders_processed)
+            .start(&orders, orders_processed, Report::new())
             .expect("Error when starting");
 
         let grain_coffee_got = coffee_maker
             .containers
-            .get_quantity_of(&"grain_coffee".to_string())
+            .get_quantity_of(&Ingredient::GrainCoffee)
             .expect("Error when locking coffee container");
 
         assert_eq!(grain_coffee_got, 50);
@@ -328,20 +473,322 @@ mod tests {
         for _ in 0..14 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
-        let coffee_maker = CoffeeMaker::new(0, 100, 50);
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
         coffee_maker
             .clone()
-            .start(&orders, orders_processed)
+            .start(&orders, orders_processed, Report::new())
             .expect("Error when starting");
 
-        let milk_got = coffee_maker.clone().containers.all["milk"]
-            .read()
-            .expect("Cant have read lock of the milk container")
-            .quantity;
+        let milk_got = coffee_maker
+            .containers
+            .get_quantity_of(&Ingredient::Milk)
+            .expect("Cant have read lock of the milk container");
 
         assert_eq!(milk_got, 50);
     }
+
+    #[test]
+    fn test06_makes_two_orders_and_adds_their_outcomes_to_the_report() {
+        let orders_list = vec![
+            Order::new(10, 10, 5, 5).with_id(0),
+            Order::new(10, 10, 500, 5).with_id(1),
+        ];
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
+        let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
+        let report = Report::new();
+
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
+        coffee_maker
+            .start(&orders, orders_processed, report.clone())
+            .expect("Error when starting");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].order_id, 0);
+        assert_eq!(outcomes[0].coffee_maker_id, 0);
+        assert_eq!(outcomes[0].status, OrderStatus::Completed);
+        assert!(outcomes[0].start_ms <= outcomes[0].end_ms);
+        assert_eq!(outcomes[1].order_id, 1);
+        assert_eq!(outcomes[1].status, OrderStatus::Failed);
+        assert_eq!(outcomes[1].reason, Some("NotEnoughIngredient".to_string()));
+    }
+
+    #[test]
+    fn test07_an_idle_dispenser_waits_for_the_orders_that_arrive_later() {
+        let mut ingredients = HashMap::new();
+        ingredients.insert("coffee".to_string(), 10);
+        let orders_list = vec![
+            Order::from(ingredients.clone()).with_id(0),
+            Order::from(ingredients).with_id(1).with_arrival_ms(200),
+        ];
+        let clock = Clock::simulated();
+        let orders = Arc::new(OrdersQueue::new().with_clock(clock.clone()));
+        let orders_clone = orders.clone();
+        let report = Report::with_clock(clock.clone());
+        let feeder_clock = clock.clone();
+        let feeder = thread::spawn(move || feed_orders(orders_list, orders_clone, feeder_clock));
+
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.dispensers = 1;
+        CoffeeMaker::with_clock(config, clock)
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when starting");
+        feeder
+            .join()
+            .expect("Error when joining")
+            .expect("Error when feeding the orders");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[1].status, OrderStatus::Completed);
+        assert_eq!(outcomes[0].end_ms, 1000);
+        assert_eq!(outcomes[1].start_ms, 1000);
+        assert_eq!(outcomes[1].queue_wait_ms, 800);
+    }
+
+    #[test]
+    fn test08_an_order_with_an_unknown_ingredient_fails_without_taking_anything() {
+        let mut ingredients = HashMap::new();
+        ingredients.insert("coffee".to_string(), 10);
+        ingredients.insert("tea".to_string(), 10);
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::from(ingredients)]));
+        let report = Report::new();
+
+        let coffee_maker =
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when starting");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes[0].status, OrderStatus::Failed);
+        assert_eq!(
+            outcomes[0].reason,
+            Some("UnknownIngredient(\"tea\")".to_string())
+        );
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(100)
+        );
+    }
+
+    #[test]
+    fn test09_a_dispenser_waits_for_a_restock_of_an_ingredient_that_cant_be_replenished() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("cocoa".to_string(), 0);
+        config.capacities.insert("cocoa".to_string(), 100);
+        config.dispensers = 1;
+        config.restock_wait_ms = 10_000;
+        let coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::new(10, 10, 5, 5)]));
+        let report = Report::new();
+
+        let running = coffee_maker.clone();
+        let running_report = report.clone();
+        let handle = thread::spawn(move || {
+            running.start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                running_report,
+            )
+        });
+        while coffee_maker.containers.get_restock_waits() == 0 {
+            thread::yield_now();
+        }
+        assert_eq!(coffee_maker.restock(&Ingredient::Cocoa, 30), Ok(30));
+        handle
+            .join()
+            .expect("Error when joining")
+            .expect("Error when starting");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes[0].status, OrderStatus::Completed);
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Cocoa),
+            Ok(25)
+        );
+    }
+
+    #[test]
+    fn test10_the_foam_that_expired_is_discarded_and_replenished() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.shelf_lives_ms.insert("foam".to_string(), 2000);
+        config.dispensers = 1;
+        let coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let orders = Arc::new(OrdersQueue::from_orders(vec![
+            Order::new(5, 5, 5, 5),
+            Order::new(5, 5, 5, 5),
+        ]));
+        let report = Report::new();
+
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when starting");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert!(outcomes
+            .iter()
+            .all(|outcome| outcome.status == OrderStatus::Completed));
+        assert_eq!(
+            coffee_maker.containers.get_wasted_of(&Ingredient::Foam),
+            Ok(95)
+        );
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Milk),
+            Ok(50)
+        );
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Foam),
+            Ok(45)
+        );
+    }
+
+    #[test]
+    fn test11_an_ingredient_below_its_low_water_mark_is_replenished_without_waiting() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.low_water_marks.insert("coffee".to_string(), 60);
+        config.dispensers = 1;
+        let coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::new(10, 0, 0, 0); 5]));
+
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                Report::new(),
+            )
+            .expect("Error when starting");
+
+        assert_eq!(coffee_maker.handler.get_waits(), 0);
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(100)
+        );
+        assert_eq!(
+            coffee_maker
+                .containers
+                .get_quantity_of(&Ingredient::GrainCoffee),
+            Ok(50)
+        );
+    }
+
+    #[test]
+    fn test12_an_ingredient_with_a_replenish_rule_of_the_configuration_is_replenished() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("crushed_ice".to_string(), 0);
+        config.containers.insert("ice".to_string(), 100);
+        config.replenish_rules.push(ReplenishRuleConfig {
+            target: "crushed_ice".to_string(),
+            source: "ice".to_string(),
+            amount: Some(40),
+            ratio: Some(0.5),
+        });
+        config.dispensers = 1;
+        let coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let mut ingredients = HashMap::new();
+        ingredients.insert("crushed_ice".to_string(), 5);
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::from(ingredients); 3]));
+        let report = Report::new();
+
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when starting");
+
+        let crushed_ice = Ingredient::Other("crushed_ice".to_string());
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert!(outcomes
+            .iter()
+            .all(|outcome| outcome.status == OrderStatus::Completed));
+        assert_eq!(coffee_maker.containers.get_quantity_of(&crushed_ice), Ok(5));
+        assert_eq!(
+            coffee_maker
+                .containers
+                .get_quantity_of(&Ingredient::Other("ice".to_string())),
+            Ok(60)
+        );
+        assert_eq!(coffee_maker.handler.get_replenished()[&crushed_ice], 20);
+    }
+
+    #[test]
+    fn test13_the_alerts_of_a_container_are_sent_to_the_sinks_added() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.dispensers = 1;
+        let mut coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let sink = MemorySink::new();
+        coffee_maker.add_alert_sink(Arc::new(sink.clone()));
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::new(0, 0, 10, 0); 10]));
+
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                Report::new(),
+            )
+            .expect("Error when starting");
+
+        let alerts: Vec<Alert> = sink
+            .get_alerts()
+            .into_iter()
+            .filter(|alert| alert.ingredient == Ingredient::Cocoa)
+            .collect();
+        assert!(alerts.windows(2).all(|pair| pair[0].level < pair[1].level));
+        let last = alerts.last().expect("The cocoa was alerted");
+        assert_eq!(last.level, AlertLevel::Empty);
+        assert_eq!(last.quantity, 0);
+    }
+
+    #[test]
+    fn test14_a_coffee_maker_with_atomic_containers_makes_the_orders_and_replenishes() {
+        let orders_list = vec![Order::new(5, 5, 5, 10); 14];
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.container_backend = ContainerBackend::Atomic;
+        let coffee_maker = CoffeeMaker::with_clock(config, Clock::simulated());
+        let report = Report::new();
+
+        coffee_maker
+            .clone()
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when starting");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes.len(), 14);
+        assert!(outcomes
+            .iter()
+            .all(|outcome| outcome.status == OrderStatus::Completed));
+        assert_eq!(
+            coffee_maker.containers.get_quantity_of(&Ingredient::Milk),
+            Ok(50)
+        );
+    }
 }
@@ -0,0 +1,458 @@
+use std::{collections::HashMap, io::ErrorKind, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::{
+    alert_sinks::{AlertSink, AlertSinkConfig, StdoutSink},
+    alerts::AlertThresholds,
+    container::ContainerBackend,
+    errors::Error,
+    ingredients::{
+        Ingredient, IngredientRegistry, ReplenishRule, BASE_INGREDIENTS, REPLENISHABLE_INGREDIENTS,
+    },
+    input_controller::InputController,
+    units::Unit,
+};
+
+const COFFEE_MAKERS: u32 = 2;
+const DISPENSERS: u32 = 3;
+const INITIAL_QUANTITY: u32 = 100;
+const INITIAL_QUANTITY_WATER: u32 = 1000;
+const VALUE_TO_REPLENISH: u32 = 50;
+const YIELD_RATIO: f64 = 1.0;
+
+/// A rule to replenish an ingredient from a resource, as it is written in the
+/// configuration. Without an amount or a ratio, the replenish value and the yield ratio of
+/// the ingredient are used.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplenishRuleConfig {
+    pub target: String,
+    pub source: String,
+    #[serde(default)]
+    pub amount: Option<u32>,
+    #[serde(default)]
+    pub ratio: Option<f64>,
+}
+
+/// Configuration of a single coffee maker.
+/// If its restock wait is not zero, its dispensers wait up to that many milliseconds for
+/// an operator to restock the ingredients that can't be replenished.
+/// The units set the unit of the containers of the ingredients added in the configuration.
+/// The ingredients with a shelf life are discarded that many milliseconds after they were
+/// added to their container. The ingredients with a low-water mark are replenished in the
+/// background once their quantity drops below it. The yield ratios set how much of an
+/// ingredient is obtained from each unit of its resource when it is replenished. The
+/// replenish rules add ingredients replenished from a resource, or change the resource of
+/// the ones that are replenished by default. The alert levels set the percentages of the
+/// capacity of the containers at or below which their level is alerted, and the alert
+/// sinks where those alerts are sent (the standard output if there is none). The container
+/// backend sets if its containers are behind a read-write lock or kept in atomics.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CoffeeMakerConfig {
+    pub id: u32,
+    pub dispensers: u32,
+    pub containers: HashMap<String, u32>,
+    pub capacities: HashMap<String, u32>,
+    pub replenish_values: HashMap<String, u32>,
+    pub alert_levels: AlertThresholds,
+    pub alert_sinks: Vec<AlertSinkConfig>,
+    pub restock_wait_ms: u64,
+    pub units: HashMap<String, Unit>,
+    pub shelf_lives_ms: HashMap<String, u64>,
+    pub low_water_marks: HashMap<String, u32>,
+    pub yield_ratios: HashMap<String, f64>,
+    pub replenish_rules: Vec<ReplenishRuleConfig>,
+    pub container_backend: ContainerBackend,
+}
+
+impl CoffeeMakerConfig {
+    /// Creates a new [`CoffeeMakerConfig`] where every container starts with the initial
+    /// quantity received (except cold water) and every ingredient is replenished with the
+    /// same value.
+    pub fn new(id_value: u32, initial_quantity: u32, replenish_value: u32) -> CoffeeMakerConfig {
+        let mut containers = HashMap::new();
+        for ingredient in BASE_INGREDIENTS {
+            let quantity = match ingredient {
+                Ingredient::ColdWater => INITIAL_QUANTITY_WATER,
+                _ => initial_quantity,
+            };
+            containers.insert(ingredient.name().to_owned(), quantity);
+        }
+
+        let mut replenish_values = HashMap::new();
+        for ingredient in REPLENISHABLE_INGREDIENTS {
+            replenish_values.insert(ingredient.name().to_owned(), replenish_value);
+        }
+
+        CoffeeMakerConfig {
+            id: id_value,
+            dispensers: DISPENSERS,
+            containers,
+            capacities: HashMap::new(),
+            replenish_values,
+            alert_levels: AlertThresholds::default(),
+            alert_sinks: Vec::new(),
+            restock_wait_ms: 0,
+            units: HashMap::new(),
+            shelf_lives_ms: HashMap::new(),
+            low_water_marks: HashMap::new(),
+            yield_ratios: HashMap::new(),
+            replenish_rules: Vec::new(),
+            container_backend: ContainerBackend::default(),
+        }
+    }
+
+    /// Returns the registry of the ingredients that have a container: the ones that every
+    /// coffee maker has and the ones added in the configuration.
+    pub fn get_registry(&self) -> IngredientRegistry {
+        let mut registry = IngredientRegistry::new();
+        for ingredient in self.containers.keys() {
+            match self.units.get(ingredient) {
+                Some(unit) => registry.register_with_unit(ingredient, *unit),
+                None => registry.register(ingredient),
+            };
+        }
+
+        registry
+    }
+
+    /// Returns the sinks where the alerts of its containers are sent, the standard output
+    /// if none is configured. Returns an error if one of them can't be created.
+    pub fn get_alert_sinks(&self) -> Result<Vec<Arc<dyn AlertSink>>, Error> {
+        if self.alert_sinks.is_empty() {
+            return Ok(vec![Arc::new(StdoutSink)]);
+        }
+
+        self.alert_sinks.iter().map(|sink| sink.build()).collect()
+    }
+
+    /// Returns the ingredients that have a container, sorted.
+    pub fn get_ingredients(&self) -> Vec<Ingredient> {
+        self.get_registry().get_all()
+    }
+
+    /// Returns the initial quantity of the container of the ingredient received,
+    /// zero if it is not configured.
+    pub fn get_initial_quantity(&self, ingredient: &Ingredient) -> u32 {
+        self.containers
+            .get(ingredient.name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the greatest quantity that the container of the ingredient received can
+    /// hold: its configured capacity or, if it is not configured, the greatest of its
+    /// initial quantity and the value used to replenish it.
+    pub fn get_capacity(&self, ingredient: &Ingredient) -> u32 {
+        match self.capacities.get(ingredient.name()) {
+            Some(capacity) => *capacity,
+            None => self
+                .get_initial_quantity(ingredient)
+                .max(self.get_replenish_value(ingredient)),
+        }
+    }
+
+    /// Returns the replenish rule of the configuration whose target is the ingredient
+    /// received, if it has one.
+    fn get_rule_config(&self, ingredient: &Ingredient) -> Option<&ReplenishRuleConfig> {
+        self.replenish_rules
+            .iter()
+            .rev()
+            .find(|rule| rule.target == ingredient.name())
+    }
+
+    /// Returns the value used to replenish the ingredient received: the amount of its
+    /// replenish rule or its replenish value, zero if none is configured.
+    pub fn get_replenish_value(&self, ingredient: &Ingredient) -> u32 {
+        if let Some(amount) = self
+            .get_rule_config(ingredient)
+            .and_then(|rule| rule.amount)
+        {
+            return amount;
+        }
+
+        self.replenish_values
+            .get(ingredient.name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the rules to replenish the ingredients: the ones of the ingredients that are
+    /// replenished by default, changed by the rules of the configuration with their same
+    /// target, followed by the other rules of the configuration in their order.
+    pub fn get_replenish_rules(&self) -> Vec<ReplenishRule> {
+        let mut targets: Vec<(Ingredient, Ingredient)> = REPLENISHABLE_INGREDIENTS
+            .into_iter()
+            .filter_map(|ingredient| Some((ingredient.clone(), ingredient.get_resource()?)))
+            .collect();
+        for rule in &self.replenish_rules {
+            let target = Ingredient::from_name(&rule.target);
+            let source = Ingredient::from_name(&rule.source);
+            match targets.iter_mut().find(|(other, _)| *other == target) {
+                Some((_, other_source)) => *other_source = source,
+                None => targets.push((target, source)),
+            }
+        }
+
+        targets
+            .into_iter()
+            .map(|(target, source)| ReplenishRule {
+                amount: self.get_replenish_value(&target),
+                ratio: self.get_yield_ratio(&target),
+                target,
+                source,
+            })
+            .collect()
+    }
+
+    /// Returns the quantity below which the ingredient received is replenished,
+    /// zero if it is only replenished when it is empty.
+    pub fn get_low_water_mark(&self, ingredient: &Ingredient) -> u32 {
+        self.low_water_marks
+            .get(ingredient.name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns how much of the ingredient received is obtained from each unit of its
+    /// resource: the ratio of its replenish rule or its yield ratio, one if none is
+    /// configured or it is not a positive number.
+    pub fn get_yield_ratio(&self, ingredient: &Ingredient) -> f64 {
+        let rule_ratio = self.get_rule_config(ingredient).and_then(|rule| rule.ratio);
+        match rule_ratio
+            .as_ref()
+            .or(self.yield_ratios.get(ingredient.name()))
+        {
+            Some(ratio) if ratio.is_finite() && *ratio > 0.0 => *ratio,
+            _ => YIELD_RATIO,
+        }
+    }
+
+    /// Returns the shelf life of the ingredient received in milliseconds,
+    /// if it has one.
+    pub fn get_shelf_life_ms(&self, ingredient: &Ingredient) -> Option<u64> {
+        self.shelf_lives_ms.get(ingredient.name()).copied()
+    }
+}
+
+impl Default for CoffeeMakerConfig {
+    fn default() -> Self {
+        Self::new(0, INITIAL_QUANTITY, VALUE_TO_REPLENISH)
+    }
+}
+
+/// Configuration of all the coffee makers of a run.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Config {
+    pub coffee_makers: Vec<CoffeeMakerConfig>,
+}
+
+impl Config {
+    /// Converts the configuration from a json string if it can,
+    /// returns an error if not.
+    pub fn deserialize(config: &str) -> Result<Config, Error> {
+        match serde_json::from_str::<Config>(config) {
+            Ok(config) => Ok(config),
+            Err(_) => Err(Error::WrongConfigFormat),
+        }
+    }
+
+    /// Reads the configuration file of the path received, absolute or relative to the
+    /// current directory, and returns its configuration if it can, returns an error if not.
+    /// Like the files of orders, a relative path that doesn't exist from the current
+    /// directory is looked for in the resources directory.
+    pub fn from_file(path: &str) -> Result<Config, Error> {
+        let config = match std::fs::read_to_string(InputController::get_path(path)) {
+            Ok(config) => config,
+            Err(err) => match err.kind() {
+                ErrorKind::NotFound => return Err(Error::ConfigFileNotFound),
+                _ => return Err(Error::CantReadConfigFile),
+            },
+        };
+
+        Config::deserialize(&config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut coffee_makers = Vec::new();
+        for id in 0..COFFEE_MAKERS {
+            coffee_makers.push(CoffeeMakerConfig::new(
+                id,
+                INITIAL_QUANTITY,
+                VALUE_TO_REPLENISH,
+            ));
+        }
+
+        Config { coffee_makers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{CoffeeMakerConfig, Config},
+        container::ContainerBackend,
+        errors::Error,
+        ingredients::{Ingredient, ReplenishRule},
+        units::Unit,
+    };
+
+    #[test]
+    fn test01_default_config_has_two_coffee_makers_with_three_dispensers() {
+        let config = Config::default();
+
+        assert_eq!(config.coffee_makers.len(), 2);
+        assert_eq!(config.coffee_makers[0].id, 0);
+        assert_eq!(config.coffee_makers[1].id, 1);
+        assert_eq!(config.coffee_makers[0].dispensers, 3);
+        assert_eq!(
+            config.coffee_makers[0].get_initial_quantity(&Ingredient::Coffee),
+            100
+        );
+        assert_eq!(
+            config.coffee_makers[0].get_initial_quantity(&Ingredient::ColdWater),
+            1000
+        );
+        assert_eq!(
+            config.coffee_makers[0].get_replenish_value(&Ingredient::Foam),
+            50
+        );
+    }
+
+    #[test]
+    fn test02_get_a_config_with_missing_fields_uses_their_default_values() {
+        let config = "{\"coffee_makers\": [{\"id\": 7, \"dispensers\": 1}]}";
+        let config = Config::deserialize(config).expect("The config is invalid");
+        let expected = CoffeeMakerConfig {
+            id: 7,
+            dispensers: 1,
+            ..CoffeeMakerConfig::default()
+        };
+
+        assert_eq!(config.coffee_makers, vec![expected]);
+    }
+
+    #[test]
+    fn test03_get_a_config_with_custom_container_levels() {
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"coffee\": 20}, \"capacities\": {\"milk\": 40}, \"replenish_values\": {\"coffee\": 5}, \"alert_levels\": {\"warning\": 30}}]}";
+        let config = Config::deserialize(config).expect("The config is invalid");
+        let coffee_maker = &config.coffee_makers[0];
+
+        assert_eq!(coffee_maker.get_initial_quantity(&Ingredient::Coffee), 20);
+        assert_eq!(coffee_maker.get_initial_quantity(&Ingredient::Milk), 0);
+        assert_eq!(coffee_maker.get_capacity(&Ingredient::Coffee), 20);
+        assert_eq!(coffee_maker.get_capacity(&Ingredient::Milk), 40);
+        assert_eq!(coffee_maker.get_replenish_value(&Ingredient::Coffee), 5);
+        assert_eq!(coffee_maker.alert_levels.warning, 30);
+        assert_eq!(coffee_maker.alert_levels.critical, 10);
+        assert_eq!(coffee_maker.get_shelf_life_ms(&Ingredient::Milk), None);
+    }
+
+    #[test]
+    fn test04_get_a_config_with_new_containers() {
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"sugar\": 30, \"tea\": 20}, \"units\": {\"tea\": \"ml\"}}]}";
+        let config = Config::deserialize(config).expect("The config is invalid");
+        let ingredients = config.coffee_makers[0].get_ingredients();
+
+        assert_eq!(ingredients.len(), 9);
+        assert!(ingredients.contains(&Ingredient::Other("sugar".to_string())));
+        assert!(ingredients.contains(&Ingredient::Coffee));
+        assert_eq!(
+            config.coffee_makers[0].get_initial_quantity(&Ingredient::Other("sugar".to_string())),
+            30
+        );
+        assert_eq!(
+            config.coffee_makers[0]
+                .get_registry()
+                .get_unit(&Ingredient::Other("tea".to_string())),
+            Unit::Milliliters
+        );
+    }
+
+    #[test]
+    fn test05_get_a_config_with_wrong_format() {
+        let result = Config::deserialize("{\"machines\": 2}").expect_err("The config is invalid");
+
+        assert_eq!(result, Error::WrongConfigFormat);
+    }
+
+    #[test]
+    fn test06_get_a_not_found_config_file() {
+        let result = Config::from_file("configuracion.json").expect_err("The file wasnt found");
+
+        assert_eq!(result, Error::ConfigFileNotFound);
+    }
+
+    #[test]
+    fn test07_get_the_replenish_rules_of_a_config() {
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"replenish_values\": {\"coffee\": 30}, \"replenish_rules\": [{\"target\": \"crushed_ice\", \"source\": \"ice\", \"amount\": 40, \"ratio\": 0.9}, {\"target\": \"coffee\", \"source\": \"decaf\", \"ratio\": 0.8}]}]}";
+        let config = Config::deserialize(config).expect("The config is invalid");
+        let rules = config.coffee_makers[0].get_replenish_rules();
+
+        assert_eq!(
+            rules,
+            vec![
+                ReplenishRule {
+                    target: Ingredient::Coffee,
+                    source: Ingredient::Other("decaf".to_string()),
+                    amount: 30,
+                    ratio: 0.8,
+                },
+                ReplenishRule {
+                    target: Ingredient::HotWater,
+                    source: Ingredient::ColdWater,
+                    amount: 0,
+                    ratio: 1.0,
+                },
+                ReplenishRule {
+                    target: Ingredient::Foam,
+                    source: Ingredient::Milk,
+                    amount: 0,
+                    ratio: 1.0,
+                },
+                ReplenishRule {
+                    target: Ingredient::Other("crushed_ice".to_string()),
+                    source: Ingredient::Other("ice".to_string()),
+                    amount: 40,
+                    ratio: 0.9,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test08_get_a_config_file_from_its_path() {
+        let config = Config::from_file("resources/config.json").expect("The file is invalid");
+
+        assert_eq!(config, Config::default());
+        assert_eq!(Config::from_file("config.json"), Ok(config));
+    }
+
+    #[test]
+    fn test09_get_a_config_of_a_directory_is_a_read_error() {
+        let result = Config::from_file("resources").expect_err("A directory cant be read");
+
+        assert_eq!(result, Error::CantReadConfigFile);
+    }
+
+    #[test]
+    fn test10_get_a_config_with_the_atomic_container_backend() {
+        let config =
+            "{\"coffee_makers\": [{\"id\": 0, \"container_backend\": \"atomic\"}, {\"id\": 1}]}";
+        let config = Config::deserialize(config).expect("The config is invalid");
+
+        assert_eq!(
+            config.coffee_makers[0].container_backend,
+            ContainerBackend::Atomic
+        );
+        assert_eq!(
+            config.coffee_makers[1].container_backend,
+            ContainerBackend::Locked
+        );
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"container_backend\": \"mutex\"}]}";
+        assert_eq!(Config::deserialize(config), Err(Error::WrongConfigFormat));
+    }
+}
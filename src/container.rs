@@ -1,36 +1,268 @@
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
 
-use crate::errors::Error;
+use serde::Deserialize;
+
+use crate::{
+    atomic_container::AtomicContainer,
+    audit::{AuditActor, AuditEntry, AuditLog, AuditOperation},
+    errors::Error,
+    ingredients::Ingredient,
+    snapshot::ContainerSnapshot,
+};
+
+/// A container that many dispensers can take from and refill at the same time.
+pub trait SharedContainer: Send + Sync {
+    /// Decrements its quantity if it has enough, returns an error if not.
+    fn dispense(&self, value: u32) -> Result<(), Error>;
+
+    /// Increments its quantity up to its capacity. Returns the quantity that was added.
+    fn refill(&self, value: u32) -> Result<u32, Error>;
+
+    /// Returns its current quantity.
+    fn get_quantity(&self) -> Result<u32, Error>;
+}
+
+/// How a shared container keeps its quantity: behind a read-write lock or in atomics.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerBackend {
+    #[default]
+    Locked,
+    Atomic,
+}
+
+impl ContainerBackend {
+    /// Creates a new container of the ingredient with this backend, with the quantity, the
+    /// capacity and the shelf life received. The atomic containers don't keep a shelf life.
+    pub fn new_container(
+        &self,
+        ingredient: Ingredient,
+        value: u32,
+        capacity: u32,
+        shelf_life_ms: Option<u64>,
+    ) -> BackendContainer {
+        match self {
+            ContainerBackend::Locked => {
+                let mut container = Container::new(ingredient, value).with_capacity(capacity);
+                if let Some(shelf_life_ms) = shelf_life_ms {
+                    container = container.with_shelf_life(shelf_life_ms);
+                }
+                BackendContainer::Locked(Arc::new(RwLock::new(container)))
+            }
+            ContainerBackend::Atomic => BackendContainer::Atomic(Arc::new(
+                AtomicContainer::new(ingredient, value).with_capacity(capacity),
+            )),
+        }
+    }
+}
+
+/// A quantity of an ingredient that was added to a container at the same time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub added_ms: u64,
+    pub quantity: u32,
+}
 
 #[derive(Debug, Clone)]
 pub struct Container {
-    pub name: String,
+    pub ingredient: Ingredient,
     pub quantity: u32,
+    pub capacity: u32,
+    pub replenishments: u32,
+    pub replenished: u32,
+    pub wasted: u32,
+    pub shelf_life_ms: Option<u64>,
+    pub batches: VecDeque<Batch>,
+    pub last_ms: u64,
+    pub audit: AuditLog,
 }
 
 impl Container {
-    /// Creates a new [`Container`].
-    pub fn new(ingredient: String, value: u32) -> Container {
+    /// Creates a new [`Container`] without a limit of quantity.
+    pub fn new(ingredient: Ingredient, value: u32) -> Container {
         Container {
-            name: ingredient,
+            ingredient,
             quantity: value,
+            capacity: u32::MAX,
+            replenishments: 0,
+            replenished: 0,
+            wasted: 0,
+            shelf_life_ms: None,
+            batches: VecDeque::new(),
+            last_ms: 0,
+            audit: AuditLog::new(),
+        }
+    }
+
+    /// Returns the same [`Container`] with the capacity received, discarding the quantity
+    /// that doesn't fit in it, the newest first.
+    pub fn with_capacity(mut self, capacity_value: u32) -> Container {
+        self.capacity = capacity_value;
+        self.quantity = self.quantity.min(capacity_value);
+        let mut excess = self
+            .batches
+            .iter()
+            .map(|batch| batch.quantity)
+            .sum::<u32>()
+            .saturating_sub(self.quantity);
+        while excess > 0 {
+            let Some(batch) = self.batches.back_mut() else {
+                break;
+            };
+            let taken = excess.min(batch.quantity);
+            batch.quantity -= taken;
+            excess -= taken;
+            if batch.quantity == 0 {
+                self.batches.pop_back();
+            }
+        }
+        self
+    }
+
+    /// Returns the same [`Container`] with the shelf life received: its quantity expires
+    /// that many milliseconds after it was added. Its current quantity was added at the
+    /// start.
+    pub fn with_shelf_life(mut self, shelf_life_ms: u64) -> Container {
+        self.shelf_life_ms = Some(shelf_life_ms);
+        self.set_quantity(self.quantity, 0);
+        self
+    }
+
+    /// Sets its quantity, up to its capacity, as if all of it was added at the time
+    /// received.
+    pub fn set_quantity(&mut self, value: u32, timestamp_ms: u64) {
+        self.quantity = value.min(self.capacity);
+        self.batches.clear();
+        self.add_batch(self.quantity, timestamp_ms);
+    }
+
+    // Keeps the time a quantity was added if the ingredient has a shelf life. A quantity
+    // added at a time before the last operation of the container, by a thread whose clock
+    // is behind, is added at the time of that operation.
+    fn add_batch(&mut self, value: u32, timestamp_ms: u64) {
+        if self.shelf_life_ms.is_some() && value > 0 {
+            self.batches.push_back(Batch {
+                added_ms: timestamp_ms.max(self.last_ms),
+                quantity: value,
+            });
         }
     }
 
+    // Removes a quantity from its batches, the oldest first.
+    fn take_from_batches(&mut self, value: u32) {
+        let mut value = value;
+        while value > 0 {
+            let Some(batch) = self.batches.front_mut() else {
+                break;
+            };
+            let taken = value.min(batch.quantity);
+            batch.quantity -= taken;
+            value -= taken;
+            if batch.quantity == 0 {
+                self.batches.pop_front();
+            }
+        }
+    }
+
+    /// Returns the quantity that expired at the time received and was not discarded yet.
+    pub fn get_expired(&self, timestamp_ms: u64) -> u32 {
+        let Some(shelf_life_ms) = self.shelf_life_ms else {
+            return 0;
+        };
+
+        self.batches
+            .iter()
+            .take_while(|batch| batch.added_ms.saturating_add(shelf_life_ms) <= timestamp_ms)
+            .map(|batch| batch.quantity)
+            .sum()
+    }
+
+    /// Returns the quantity that has not expired at the time received.
+    pub fn get_fresh_quantity(&self, timestamp_ms: u64) -> u32 {
+        self.quantity - self.get_expired(timestamp_ms)
+    }
+
+    /// Returns the quantity that can be added to the container until it is full.
+    pub fn get_free_space(&self) -> u32 {
+        self.capacity.saturating_sub(self.quantity)
+    }
+
+    /// Returns the percentage of its capacity that is filled.
+    pub fn get_fill_percentage(&self) -> u32 {
+        match self.capacity {
+            0 => 0,
+            capacity => (self.quantity as u64 * 100 / capacity as u64) as u32,
+        }
+    }
+
+    // Adds an operation to its audit log and keeps the time of its last operation.
+    fn record(
+        &mut self,
+        operation: AuditOperation,
+        actor: AuditActor,
+        amount: u32,
+        before: u32,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) {
+        self.last_ms = self.last_ms.max(timestamp_ms);
+        self.audit.record(AuditEntry {
+            timestamp_ms,
+            coffee_maker_id,
+            ingredient: self.ingredient.clone(),
+            actor,
+            operation,
+            amount,
+            before,
+            after: self.quantity,
+        });
+    }
+
     /// Decrements the quantity of its ingredient if can, returns an error if not.
+    /// The dispense is added to its audit log at the time received, made by the dispenser
+    /// received or by the ingredient handler if there is not one.
     pub fn dispense(
         &mut self,
         value: u32,
         dispenser_id: Option<u32>,
         coffee_maker_id: u32,
+        timestamp_ms: u64,
     ) -> Result<(), Error> {
         println!(
             "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: GETTING {:?} OF {:?} FROM {:?}",
-            dispenser_id, coffee_maker_id, value, self.name, self.quantity
+            dispenser_id,
+            coffee_maker_id,
+            value,
+            self.ingredient.name(),
+            self.quantity
+        );
+        let before = self.quantity;
+        self.take(value)?;
+        let actor = match dispenser_id {
+            Some(dispenser_id) => AuditActor::Dispenser(dispenser_id),
+            None => AuditActor::IngredientHandler,
+        };
+        self.record(
+            AuditOperation::Dispense,
+            actor,
+            value,
+            before,
+            coffee_maker_id,
+            timestamp_ms,
         );
+
+        Ok(())
+    }
+
+    /// Decrements the quantity of its ingredient right away if can, returns an error if not.
+    /// The oldest quantity is taken first.
+    pub fn take(&mut self, value: u32) -> Result<(), Error> {
         if self.quantity >= value {
             self.quantity -= value;
-            std::thread::sleep(Duration::from_secs(1));
+            self.take_from_batches(value);
         } else {
             return Err(Error::NotEnoughIngredient);
         };
@@ -38,34 +270,388 @@ impl Container {
         Ok(())
     }
 
-    // Increments the quantity of its ingredient if can, returns an error if not
-    pub fn replenish(&mut self, value: u32, coffee_maker_id: u32) -> Result<(), Error> {
+    /// Increments the quantity of its ingredient right away up to its capacity, without
+    /// counting a replenishment. Returns the quantity that was added.
+    pub fn add(&mut self, value: u32) -> u32 {
+        let added = value.min(self.get_free_space());
+        self.quantity += added;
+        self.add_batch(added, self.last_ms);
+        added
+    }
+
+    // Increments the quantity of its ingredient up to its capacity, the quantity that
+    // doesn't fit is not added, and counts the replenishment. The replenishment is added
+    // to its audit log at the time received. Returns the quantity that was added.
+    pub fn replenish(
+        &mut self,
+        value: u32,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) -> Result<u32, Error> {
+        let added = value.min(self.get_free_space());
         println!(
             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: INCREMENTING {:?} OF {:?} FROM {:?}",
-            coffee_maker_id, value, self.name, self.quantity
+            coffee_maker_id,
+            added,
+            self.ingredient.name(),
+            self.quantity
+        );
+        if added < value {
+            println!(
+                "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {:?} OF {:?} DOESNT FIT IN THE CONTAINER",
+                coffee_maker_id,
+                value - added,
+                self.ingredient.name()
+            );
+        }
+        let before = self.quantity;
+        self.quantity += added;
+        self.add_batch(added, timestamp_ms);
+        if added > 0 {
+            self.replenishments += 1;
+            self.replenished += added;
+        }
+        self.record(
+            AuditOperation::Replenish,
+            AuditActor::IngredientHandler,
+            added,
+            before,
+            coffee_maker_id,
+            timestamp_ms,
         );
-        self.quantity += value;
-        std::thread::sleep(Duration::from_secs(1));
 
-        Ok(())
+        Ok(added)
+    }
+
+    // Increments the quantity of its ingredient with the stock brought by an operator, up
+    // to its capacity. It is not counted as a replenishment. Returns the quantity that was
+    // added.
+    pub fn restock(
+        &mut self,
+        value: u32,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) -> Result<u32, Error> {
+        let added = value.min(self.get_free_space());
+        println!(
+            "[OPERATOR] OF [COFFEE MAKER {:?}]: RESTOCKING {:?} OF {:?} FROM {:?}",
+            coffee_maker_id,
+            added,
+            self.ingredient.name(),
+            self.quantity
+        );
+        let before = self.quantity;
+        self.quantity += added;
+        self.add_batch(added, timestamp_ms);
+        self.record(
+            AuditOperation::Restock,
+            AuditActor::Operator,
+            added,
+            before,
+            coffee_maker_id,
+            timestamp_ms,
+        );
+
+        Ok(added)
+    }
+
+    // Discards the quantity that expired at the time received and counts it as wasted.
+    // The discard is added to its audit log. Returns the quantity that was discarded.
+    pub fn discard_expired(&mut self, coffee_maker_id: u32, timestamp_ms: u64) -> u32 {
+        let expired = self.get_expired(timestamp_ms);
+        if expired == 0 {
+            return 0;
+        }
+        println!(
+            "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: DISCARDING {:?} OF {:?} THAT EXPIRED",
+            coffee_maker_id,
+            expired,
+            self.ingredient.name()
+        );
+        let before = self.quantity;
+        self.quantity -= expired;
+        self.take_from_batches(expired);
+        self.wasted += expired;
+        self.record(
+            AuditOperation::Discard,
+            AuditActor::IngredientHandler,
+            expired,
+            before,
+            coffee_maker_id,
+            timestamp_ms,
+        );
+
+        expired
+    }
+}
+
+impl SharedContainer for RwLock<Container> {
+    fn dispense(&self, value: u32) -> Result<(), Error> {
+        match self.write() {
+            Ok(mut container) => container.take(value),
+            Err(_) => Err(Error::CantWriteContainerLock),
+        }
+    }
+
+    fn refill(&self, value: u32) -> Result<u32, Error> {
+        match self.write() {
+            Ok(mut container) => Ok(container.add(value)),
+            Err(_) => Err(Error::CantWriteContainerLock),
+        }
+    }
+
+    fn get_quantity(&self) -> Result<u32, Error> {
+        match self.read() {
+            Ok(container) => Ok(container.quantity),
+            Err(_) => Err(Error::CantReadContainerLock),
+        }
+    }
+}
+
+/// A container of a coffee maker with the backend of its configuration: a [`Container`]
+/// behind a read-write lock, or an [`AtomicContainer`] that has no audit log nor shelf life.
+#[derive(Debug, Clone)]
+pub enum BackendContainer {
+    Locked(Arc<RwLock<Container>>),
+    Atomic(Arc<AtomicContainer>),
+}
+
+impl BackendContainer {
+    /// Decrements the quantity of its ingredient if can, after discarding the quantity that
+    /// expired at the time received, returns an error if not.
+    pub fn dispense(
+        &self,
+        value: u32,
+        dispenser_id: Option<u32>,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) -> Result<(), Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.write() {
+                Ok(mut container) => {
+                    container.discard_expired(coffee_maker_id, timestamp_ms);
+                    container.dispense(value, dispenser_id, coffee_maker_id, timestamp_ms)
+                }
+                Err(_) => Err(Error::CantWriteContainerLock),
+            },
+            BackendContainer::Atomic(container) => {
+                let before = container.take(value)?;
+                container.print_dispense(value, before, dispenser_id, coffee_maker_id);
+                Ok(())
+            }
+        }
+    }
+
+    /// Increments the quantity of its ingredient up to its capacity, after discarding the
+    /// quantity that expired at the time received, and counts the replenishment. Returns
+    /// the quantity that was added.
+    pub fn replenish(
+        &self,
+        value: u32,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.write() {
+                Ok(mut container) => {
+                    container.discard_expired(coffee_maker_id, timestamp_ms);
+                    container.replenish(value, coffee_maker_id, timestamp_ms)
+                }
+                Err(_) => Err(Error::CantWriteContainerLock),
+            },
+            BackendContainer::Atomic(container) => {
+                let added = container.refill(value)?;
+                println!(
+                    "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: INCREMENTING {:?} OF {:?}",
+                    coffee_maker_id,
+                    added,
+                    container.ingredient.name()
+                );
+                Ok(added)
+            }
+        }
+    }
+
+    /// Increments the quantity of its ingredient with the stock brought by an operator up
+    /// to its capacity, after discarding the quantity that expired at the time received.
+    /// Returns the quantity that was added.
+    pub fn restock(
+        &self,
+        value: u32,
+        coffee_maker_id: u32,
+        timestamp_ms: u64,
+    ) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.write() {
+                Ok(mut container) => {
+                    container.discard_expired(coffee_maker_id, timestamp_ms);
+                    container.restock(value, coffee_maker_id, timestamp_ms)
+                }
+                Err(_) => Err(Error::CantWriteContainerLock),
+            },
+            BackendContainer::Atomic(container) => {
+                let added = container.restock(value);
+                println!(
+                    "[OPERATOR] OF [COFFEE MAKER {:?}]: RESTOCKING {:?} OF {:?}",
+                    coffee_maker_id,
+                    added,
+                    container.ingredient.name()
+                );
+                Ok(added)
+            }
+        }
+    }
+
+    /// Returns the quantity of its ingredient that has not expired at the time received.
+    pub fn get_fresh_quantity(&self, timestamp_ms: u64) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => Ok(container.get_fresh_quantity(timestamp_ms)),
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(container) => container.get_quantity(),
+        }
+    }
+
+    /// Returns the quantity that can be added until it is full once the quantity that
+    /// expired at the time received is discarded.
+    pub fn get_free_space(&self, timestamp_ms: u64) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => {
+                    Ok(container.get_free_space() + container.get_expired(timestamp_ms))
+                }
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(container) => Ok(container.get_free_space()),
+        }
+    }
+
+    /// Returns the greatest quantity it can hold.
+    pub fn get_capacity(&self) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => Ok(container.capacity),
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(container) => Ok(container.capacity),
+        }
+    }
+
+    /// Returns the percentage of its capacity that is filled.
+    pub fn get_fill_percentage(&self) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => Ok(container.get_fill_percentage()),
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(container) => Ok(container.get_fill_percentage()),
+        }
+    }
+
+    /// Returns the quantity that was discarded because it expired.
+    pub fn get_wasted(&self) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => Ok(container.wasted),
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(_) => Ok(0),
+        }
+    }
+
+    /// Returns its audit log, empty if it doesn't keep one.
+    pub fn get_audit_log(&self) -> Result<AuditLog, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.read() {
+                Ok(container) => Ok(container.audit.clone()),
+                Err(_) => Err(Error::CantReadContainerLock),
+            },
+            BackendContainer::Atomic(_) => Ok(AuditLog::new()),
+        }
+    }
+
+    /// Discards the quantity that expired at the time received. Returns the quantity that
+    /// was discarded.
+    pub fn discard_expired(&self, coffee_maker_id: u32, timestamp_ms: u64) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.write() {
+                Ok(mut container) => Ok(container.discard_expired(coffee_maker_id, timestamp_ms)),
+                Err(_) => Err(Error::CantWriteContainerLock),
+            },
+            BackendContainer::Atomic(_) => Ok(0),
+        }
+    }
+
+    /// Restores the state of the snapshot received, the quantity that doesn't fit is
+    /// discarded. The quantity restored is fresh from the time received.
+    pub fn restore(&self, snapshot: &ContainerSnapshot, timestamp_ms: u64) -> Result<(), Error> {
+        match self {
+            BackendContainer::Locked(container) => match container.write() {
+                Ok(mut container) => {
+                    container.set_quantity(snapshot.quantity, timestamp_ms);
+                    container.replenishments = snapshot.replenishments;
+                    container.replenished = snapshot.replenished;
+                    container.wasted = snapshot.wasted;
+                    Ok(())
+                }
+                Err(_) => Err(Error::CantWriteContainerLock),
+            },
+            BackendContainer::Atomic(container) => {
+                container.restore(
+                    snapshot.quantity,
+                    snapshot.replenishments,
+                    snapshot.replenished,
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+impl SharedContainer for BackendContainer {
+    fn dispense(&self, value: u32) -> Result<(), Error> {
+        match self {
+            BackendContainer::Locked(container) => container.dispense(value),
+            BackendContainer::Atomic(container) => container.dispense(value),
+        }
+    }
+
+    fn refill(&self, value: u32) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => container.refill(value),
+            BackendContainer::Atomic(container) => container.refill(value),
+        }
+    }
+
+    fn get_quantity(&self) -> Result<u32, Error> {
+        match self {
+            BackendContainer::Locked(container) => container.get_quantity(),
+            BackendContainer::Atomic(container) => container.get_quantity(),
+        }
     }
 }
 
 impl Default for Container {
     fn default() -> Self {
-        Self::new("".to_string(), 0)
+        Self::new(Ingredient::Other(String::new()), 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{container::Container, errors::Error};
+    use crate::{
+        audit::{AuditActor, AuditOperation},
+        container::{Container, ContainerBackend, SharedContainer},
+        errors::Error,
+        ingredients::Ingredient,
+    };
 
     #[test]
     fn test01_get_a_value_lower_than_its_quantity_and_update_its_quantity() {
-        let mut container = Container::new("coffe".to_string(), 10);
+        let mut container = Container::new(Ingredient::Coffee, 10);
         container
-            .dispense(5, Some(0), 0)
+            .dispense(5, Some(0), 0, 0)
             .expect("There is not enough ingredient to make the order");
         let quantity_expected = 5;
         assert_eq!(container.quantity, quantity_expected);
@@ -73,9 +659,9 @@ mod tests {
 
     #[test]
     fn test02_get_a_value_equal_than_its_quantity_and_update_its_quantity() {
-        let mut container = Container::new("coffe".to_string(), 10);
+        let mut container = Container::new(Ingredient::Coffee, 10);
         container
-            .dispense(10, Some(0), 0)
+            .dispense(10, Some(0), 0, 0)
             .expect("There is not enough ingredient to make the order");
         let quantity_expected = 0;
         assert_eq!(container.quantity, quantity_expected);
@@ -83,9 +669,9 @@ mod tests {
 
     #[test]
     fn test03_get_a_value_greater_than_its_quantity_and_returns_an_error() {
-        let mut container = Container::new("coffe".to_string(), 10);
+        let mut container = Container::new(Ingredient::Coffee, 10);
         let error_got = container
-            .dispense(15, Some(0), 0)
+            .dispense(15, Some(0), 0, 0)
             .expect_err("There is not enough ingredient to make the order");
         let error_expected = Error::NotEnoughIngredient;
         assert_eq!(error_got, error_expected);
@@ -93,9 +679,94 @@ mod tests {
 
     #[test]
     fn test04_increase_its_quantity_when_replenishing() {
-        let mut container = Container::new("coffe".to_string(), 10);
-        container.replenish(5, 0).expect("Error when replinishing");
+        let mut container = Container::new(Ingredient::Coffee, 10);
+        container
+            .replenish(5, 0, 0)
+            .expect("Error when replinishing");
         let quantity_expected = 15;
         assert_eq!(container.quantity, quantity_expected);
     }
+
+    #[test]
+    fn test05_replenish_up_to_its_capacity_and_return_the_quantity_added() {
+        let mut container = Container::new(Ingredient::Coffee, 10).with_capacity(12);
+        let added = container
+            .replenish(5, 0, 0)
+            .expect("Error when replinishing");
+
+        assert_eq!(added, 2);
+        assert_eq!(container.quantity, 12);
+        assert_eq!(container.get_free_space(), 0);
+        assert_eq!(container.get_fill_percentage(), 100);
+        assert_eq!(container.replenish(5, 0, 0), Ok(0));
+        assert_eq!(container.replenishments, 1);
+        assert_eq!(container.replenished, 2);
+    }
+
+    #[test]
+    fn test06_restock_up_to_its_capacity_without_counting_a_replenishment() {
+        let mut container = Container::new(Ingredient::Cocoa, 0).with_capacity(100);
+        let added = container.restock(150, 0, 0).expect("Error when restocking");
+
+        assert_eq!(added, 100);
+        assert_eq!(container.quantity, 100);
+        assert_eq!(container.replenishments, 0);
+        assert_eq!(container.audit.get_entries()[0].actor, AuditActor::Operator);
+    }
+
+    #[test]
+    fn test07_discard_the_quantity_that_expired_and_count_it_as_wasted() {
+        let mut container = Container::new(Ingredient::Milk, 30)
+            .with_capacity(100)
+            .with_shelf_life(1000);
+        container
+            .replenish(20, 0, 500)
+            .expect("Error when replinishing");
+        container
+            .dispense(10, Some(0), 0, 600)
+            .expect("There is enough milk");
+
+        assert_eq!(container.get_fresh_quantity(999), 40);
+        assert_eq!(container.get_fresh_quantity(1000), 20);
+        assert_eq!(container.discard_expired(0, 1200), 20);
+        assert_eq!(container.quantity, 20);
+        assert_eq!(container.wasted, 20);
+        assert_eq!(container.discard_expired(0, 1400), 0);
+        assert_eq!(container.discard_expired(0, 1500), 20);
+        assert_eq!(container.quantity, 0);
+        let last = container.audit.get_entries().last().cloned();
+        assert_eq!(
+            last.map(|entry| entry.operation),
+            Some(AuditOperation::Discard)
+        );
+    }
+
+    #[test]
+    fn test08_a_shared_container_of_any_backend_dispenses_and_refills_up_to_its_capacity() {
+        for backend in [ContainerBackend::Locked, ContainerBackend::Atomic] {
+            let container = backend.new_container(Ingredient::Coffee, 10, 20, None);
+
+            assert_eq!(SharedContainer::dispense(&container, 4), Ok(()));
+            assert_eq!(
+                SharedContainer::dispense(&container, 7),
+                Err(Error::NotEnoughIngredient)
+            );
+            assert_eq!(container.refill(30), Ok(14));
+            assert_eq!(container.get_quantity(), Ok(20));
+        }
+    }
+
+    #[test]
+    fn test09_a_container_with_a_shelf_life_and_a_smaller_capacity_only_expires_what_it_has() {
+        let mut container = Container::new(Ingredient::Milk, 50)
+            .with_shelf_life(1000)
+            .with_capacity(30);
+
+        assert_eq!(container.quantity, 30);
+        assert_eq!(container.get_fresh_quantity(999), 30);
+        assert_eq!(container.get_fresh_quantity(1000), 0);
+        assert_eq!(container.discard_expired(0, 1000), 30);
+        assert_eq!(container.quantity, 0);
+        assert_eq!(container.wasted, 30);
+    }
 }
@@ -1,114 +1,525 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::Duration,
 };
 
-use crate::{container::Container, errors::Error};
+use crate::{
+    atomic_container::AtomicContainer,
+    audit::AuditLog,
+    clock::Clock,
+    config::CoffeeMakerConfig,
+    container::{BackendContainer, SharedContainer},
+    errors::Error,
+    ingredients::Ingredient,
+    snapshot::ContainerSnapshot,
+};
 
-const COFFEE: &str = "coffee";
-const HOT_WATER: &str = "hot_water";
-const COCOA: &str = "cocoa";
-const FOAM: &str = "foam";
-const GRAIN_COFFEE: &str = "grain_coffee";
-const MILK: &str = "milk";
-const COLD_WATER: &str = "cold_water";
-const INITIAL_QUANTITY_WATER: u32 = 1000;
+const DISPENSE_TIME: Duration = Duration::from_secs(1);
+const REPLENISH_TIME: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone)]
 pub struct Containers {
-    pub all: HashMap<String, Arc<RwLock<Container>>>,
+    pub all: HashMap<Ingredient, BackendContainer>,
+    pub clock: Clock,
+    restocks: Arc<(Mutex<u64>, Condvar)>,
+    restock_waits: Arc<AtomicU32>,
 }
 
 impl Containers {
-    /// Creates a new [`Containers`].
-    pub fn new(max_value: u32) -> Containers {
+    /// Creates a new [`Containers`] with a container for every ingredient of the
+    /// configuration, with its backend and capacity and filled with its initial quantity.
+    pub fn new(config: &CoffeeMakerConfig) -> Containers {
+        Containers::with_clock(config, Clock::real())
+    }
+
+    /// Creates a new [`Containers`] like [`Containers::new`] that takes its time to dispense
+    /// and to replenish from the clock received.
+    pub fn with_clock(config: &CoffeeMakerConfig, clock: Clock) -> Containers {
         let mut containers = HashMap::new();
-        containers.insert(
-            COFFEE.to_owned(),
-            Arc::new(RwLock::new(Container::new(COFFEE.to_owned(), max_value))),
-        );
-        containers.insert(
-            HOT_WATER.to_owned(),
-            Arc::new(RwLock::new(Container::new(HOT_WATER.to_owned(), max_value))),
-        );
-        containers.insert(
-            COCOA.to_owned(),
-            Arc::new(RwLock::new(Container::new(COCOA.to_owned(), max_value))),
-        );
-        containers.insert(
-            FOAM.to_owned(),
-            Arc::new(RwLock::new(Container::new(FOAM.to_owned(), max_value))),
-        );
-        containers.insert(
-            GRAIN_COFFEE.to_owned(),
-            Arc::new(RwLock::new(Container::new(
-                GRAIN_COFFEE.to_owned(),
-                max_value,
-            ))),
-        );
-        containers.insert(
-            MILK.to_owned(),
-            Arc::new(RwLock::new(Container::new(MILK.to_owned(), max_value))),
-        );
-        containers.insert(
-            COLD_WATER.to_owned(),
-            Arc::new(RwLock::new(Container::new(
-                COLD_WATER.to_owned(),
-                INITIAL_QUANTITY_WATER,
-            ))),
-        );
+        for ingredient in config.get_ingredients() {
+            let container = config.container_backend.new_container(
+                ingredient.clone(),
+                config.get_initial_quantity(&ingredient),
+                config.get_capacity(&ingredient),
+                config.get_shelf_life_ms(&ingredient),
+            );
+            containers.insert(ingredient, container);
+        }
+
+        Containers {
+            all: containers,
+            clock,
+            restocks: Arc::new((Mutex::new(0), Condvar::new())),
+            restock_waits: Arc::new(AtomicU32::new(0)),
+        }
+    }
 
-        Containers { all: containers }
+    // Gets the container of an ingredient.
+    fn get_container(&self, ingredient: &Ingredient) -> Result<&BackendContainer, Error> {
+        match self.all.get(ingredient) {
+            Some(container) => Ok(container),
+            None => Err(Error::UnknownIngredient(ingredient.name().to_owned())),
+        }
     }
 
     // Calls to the container of the ingredient that receives to decrement its quantity.
+    // The quantity that expired is discarded first. The dispense takes its time once the
+    // container is released.
     pub fn get_ingredient(
         self,
-        ingredient: &String,
+        ingredient: &Ingredient,
         value: u32,
         dispenser_id: Option<u32>,
         coffee_maker_id: u32,
     ) -> Result<(), Error> {
-        if let Ok(mut container) = self.all[ingredient].write() {
-            container.dispense(value, dispenser_id, coffee_maker_id)?;
-        } else {
-            return Err(Error::CantWriteContainerLock);
+        self.get_container(ingredient)?.dispense(
+            value,
+            dispenser_id,
+            coffee_maker_id,
+            self.clock.now_ms(),
+        )?;
+        self.clock.sleep(DISPENSE_TIME);
+
+        Ok(())
+    }
+
+    // Groups the quantities of the same ingredient, sorted by ingredient.
+    fn group_by_ingredient(ingredients: &[(Ingredient, u32)]) -> BTreeMap<&Ingredient, u32> {
+        let mut grouped = BTreeMap::new();
+        for (ingredient, value) in ingredients {
+            *grouped.entry(ingredient).or_default() += value;
+        }
+
+        grouped
+    }
+
+    // Takes the quantity of every atomic container received if all of them have enough,
+    // or gives back what was taken and returns false if any of them has not.
+    fn take_atomics(
+        containers: &[(&Arc<AtomicContainer>, u32)],
+        dispenser_id: u32,
+        coffee_maker_id: u32,
+    ) -> bool {
+        let mut taken = Vec::new();
+        for (container, value) in containers {
+            match container.take(*value) {
+                Ok(before) => taken.push((container, *value, before)),
+                Err(_) => {
+                    for (container, value, _) in taken {
+                        container.give_back(value);
+                    }
+                    return false;
+                }
+            }
         }
+        for (container, value, before) in taken {
+            container.print_dispense(value, before, Some(dispenser_id), coffee_maker_id);
+        }
+
+        true
+    }
 
+    // Takes the quantity of every ingredient received from its container if all of them
+    // have enough, or takes nothing and returns an error if any of them has not.
+    // The locked containers are always locked in the order of their ingredients, so two
+    // dispensers can't wait for each other, and the atomic ones are taken while they are
+    // locked and given back if any of them has not enough. The quantity that expired is
+    // discarded first.
+    pub fn reserve(
+        &self,
+        ingredients: &[(Ingredient, u32)],
+        dispenser_id: u32,
+        coffee_maker_id: u32,
+    ) -> Result<(), Error> {
+        let ingredients = Containers::group_by_ingredient(ingredients);
+        let mut locked = Vec::new();
+        let mut atomics = Vec::new();
+        for (ingredient, value) in &ingredients {
+            match self.get_container(ingredient)? {
+                BackendContainer::Locked(container) => match container.write() {
+                    Ok(container) => locked.push((container, *value)),
+                    Err(_) => return Err(Error::CantWriteContainerLock),
+                },
+                BackendContainer::Atomic(container) => atomics.push((container, *value)),
+            }
+        }
+        let now_ms = self.clock.now_ms();
+        for (container, _) in locked.iter_mut() {
+            container.discard_expired(coffee_maker_id, now_ms);
+        }
+
+        if locked
+            .iter()
+            .any(|(container, value)| container.quantity < *value)
+            || !Containers::take_atomics(&atomics, dispenser_id, coffee_maker_id)
+        {
+            println!(
+                "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: NOT ENOUGH INGREDIENTS, NOTHING WAS TAKEN",
+                dispenser_id, coffee_maker_id
+            );
+            return Err(Error::NotEnoughIngredient);
+        }
+        for (container, value) in locked.iter_mut() {
+            container.dispense(*value, Some(dispenser_id), coffee_maker_id, now_ms)?;
+        }
+        drop(locked);
+
+        self.clock.sleep(DISPENSE_TIME * ingredients.len() as u32);
         Ok(())
     }
 
-    // Calls to the container of the ingredient that receives to increment its quantity.
+    // Returns the ingredients received whose containers don't have enough quantity that
+    // has not expired.
+    pub fn get_missing(&self, ingredients: &[(Ingredient, u32)]) -> Result<Vec<Ingredient>, Error> {
+        let mut missing = Vec::new();
+        for (ingredient, value) in Containers::group_by_ingredient(ingredients) {
+            if self.get_quantity_of(ingredient)? < value {
+                missing.push(ingredient.to_owned());
+            }
+        }
+
+        Ok(missing)
+    }
+
+    // Calls to the container of the ingredient that receives to increment its quantity,
+    // after discarding the quantity that expired. Returns the quantity that was added.
+    // The replenish takes its time once the container is released.
     pub fn replenish_ingredient(
         self,
-        ingredient: &String,
+        ingredient: &Ingredient,
         value: u32,
         coffee_maker_id: u32,
-    ) -> Result<(), Error> {
-        if let Ok(mut container) = self.all[ingredient].write() {
-            container.replenish(value, coffee_maker_id)?;
+    ) -> Result<u32, Error> {
+        let added = self.get_container(ingredient)?.replenish(
+            value,
+            coffee_maker_id,
+            self.clock.now_ms(),
+        )?;
+        self.clock.sleep(REPLENISH_TIME);
+
+        Ok(added)
+    }
+
+    // Calls to the container of the ingredient that receives to add the stock brought by an
+    // operator, after discarding the quantity that expired, and wakes up the dispensers that
+    // wait for a restock. Returns the quantity that was added.
+    pub fn restock(
+        &self,
+        ingredient: &Ingredient,
+        value: u32,
+        coffee_maker_id: u32,
+    ) -> Result<u32, Error> {
+        let added =
+            self.get_container(ingredient)?
+                .restock(value, coffee_maker_id, self.clock.now_ms())?;
+
+        let (restocks_lock, condvar) = &*self.restocks;
+        if let Ok(mut restocks) = restocks_lock.lock() {
+            *restocks += 1;
         } else {
-            return Err(Error::CantWriteContainerLock);
+            return Err(Error::CantHaveRestocksLock);
+        }
+        condvar.notify_all();
+
+        Ok(added)
+    }
+
+    // Gets how many restocks were made in any of the containers.
+    pub fn get_restocks(&self) -> Result<u64, Error> {
+        let (restocks_lock, _) = &*self.restocks;
+        match restocks_lock.lock() {
+            Ok(restocks) => Ok(*restocks),
+            Err(_) => Err(Error::CantHaveRestocksLock),
+        }
+    }
+
+    // Waits until there are more restocks than the ones received, or the timeout passed.
+    // Returns true if a restock was made. The wait is counted.
+    pub fn wait_for_restock(&self, restocks: u64, timeout: Duration) -> Result<bool, Error> {
+        let (restocks_lock, condvar) = &*self.restocks;
+        let Ok(current) = restocks_lock.lock() else {
+            return Err(Error::CantHaveRestocksLock);
+        };
+        self.restock_waits.fetch_add(1, Ordering::SeqCst);
+        match condvar.wait_timeout_while(current, timeout, |current| *current == restocks) {
+            Ok((current, _)) => Ok(*current != restocks),
+            Err(_) => Err(Error::CantHaveRestocksLock),
+        }
+    }
+
+    // Gets how many times a dispenser started to wait for a restock.
+    pub fn get_restock_waits(&self) -> u32 {
+        self.restock_waits.load(Ordering::SeqCst)
+    }
+
+    // Gets the quantity that can be added to the container of an ingredient until it is
+    // full, once the quantity that expired is discarded.
+    pub fn get_free_space_of(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        self.get_container(ingredient)?
+            .get_free_space(self.clock.now_ms())
+    }
+
+    // Gets the greatest quantity that the container of an ingredient can hold.
+    pub fn get_capacity_of(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        self.get_container(ingredient)?.get_capacity()
+    }
+
+    // Gets the percentage of the capacity of the container of an ingredient that is filled.
+    pub fn get_fill_percentage_of(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        self.get_container(ingredient)?.get_fill_percentage()
+    }
+
+    // Gets the state of all the containers at the same time. The locked containers are
+    // locked in the order of their ingredients, like when reserving.
+    pub fn get_snapshot(&self) -> Result<BTreeMap<Ingredient, ContainerSnapshot>, Error> {
+        let mut ingredients: Vec<&Ingredient> = self.all.keys().collect();
+        ingredients.sort();
+        let mut guards = Vec::new();
+        let mut snapshots = BTreeMap::new();
+        for ingredient in ingredients {
+            let snapshot = match self.get_container(ingredient)? {
+                BackendContainer::Locked(container) => match container.read() {
+                    Ok(container) => {
+                        let snapshot = ContainerSnapshot {
+                            quantity: container.quantity,
+                            replenishments: container.replenishments,
+                            replenished: container.replenished,
+                            wasted: container.wasted,
+                        };
+                        guards.push(container);
+                        snapshot
+                    }
+                    Err(_) => return Err(Error::CantReadContainerLock),
+                },
+                BackendContainer::Atomic(container) => ContainerSnapshot {
+                    quantity: container.get_quantity()?,
+                    replenishments: container.get_replenishments(),
+                    replenished: container.get_replenished(),
+                    wasted: 0,
+                },
+            };
+            snapshots.insert(ingredient.clone(), snapshot);
+        }
+
+        Ok(snapshots)
+    }
+
+    // Restores the state of the containers received, the quantity that doesn't fit in a
+    // container is discarded. The quantity restored is fresh from now.
+    pub fn restore(
+        &self,
+        snapshots: &BTreeMap<Ingredient, ContainerSnapshot>,
+    ) -> Result<(), Error> {
+        for (ingredient, snapshot) in snapshots {
+            self.get_container(ingredient)?
+                .restore(snapshot, self.clock.now_ms())?;
         }
 
         Ok(())
     }
 
-    // Gets the quantity of an ingredient
-    pub fn get_quantity_of(&self, ingredient: &String) -> Result<u32, Error> {
-        let quantity;
-        if let Ok(container) = self.all[ingredient].read() {
-            quantity = container.quantity;
-        } else {
-            return Err(Error::CantReadContainerLock);
+    // Gets the audit log of all the containers, sorted by the time of their operations.
+    pub fn get_audit_log(&self) -> Result<AuditLog, Error> {
+        let mut logs = Vec::new();
+        for container in self.all.values() {
+            logs.push(container.get_audit_log()?);
         }
 
-        Ok(quantity)
+        Ok(AuditLog::merge(&logs))
+    }
+
+    // Gets the quantity of an ingredient that has not expired
+    pub fn get_quantity_of(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        self.get_container(ingredient)?
+            .get_fresh_quantity(self.clock.now_ms())
+    }
+
+    // Discards the quantity that expired in all the containers. Returns the quantity that
+    // was discarded of every ingredient.
+    pub fn discard_expired(&self, coffee_maker_id: u32) -> Result<HashMap<Ingredient, u32>, Error> {
+        let mut discarded = HashMap::new();
+        for (ingredient, container) in &self.all {
+            discarded.insert(
+                ingredient.to_owned(),
+                container.discard_expired(coffee_maker_id, self.clock.now_ms())?,
+            );
+        }
+
+        Ok(discarded)
+    }
+
+    // Gets the quantity of an ingredient that was discarded because it expired
+    pub fn get_wasted_of(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        self.get_container(ingredient)?.get_wasted()
     }
 }
 
 impl Default for Containers {
     fn default() -> Self {
-        Self::new(100)
+        Self::new(&CoffeeMakerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        audit::{AuditActor, AuditOperation},
+        clock::Clock,
+        config::CoffeeMakerConfig,
+        container::ContainerBackend,
+        containers::Containers,
+        errors::Error,
+        ingredients::Ingredient,
+    };
+
+    fn get_order(coffee: u32, cocoa: u32) -> Vec<(Ingredient, u32)> {
+        vec![(Ingredient::Coffee, coffee), (Ingredient::Cocoa, cocoa)]
+    }
+
+    #[test]
+    fn test01_reserve_takes_every_ingredient() {
+        let containers =
+            Containers::with_clock(&CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
+        containers
+            .reserve(&get_order(10, 5), 0, 0)
+            .expect("There are enough ingredients");
+
+        assert_eq!(containers.get_quantity_of(&Ingredient::Coffee), Ok(90));
+        assert_eq!(containers.get_quantity_of(&Ingredient::Cocoa), Ok(95));
+    }
+
+    #[test]
+    fn test02_reserve_takes_nothing_if_an_ingredient_is_missing() {
+        let containers =
+            Containers::with_clock(&CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
+        let result = containers
+            .reserve(&get_order(10, 500), 0, 0)
+            .expect_err("There is not enough cocoa");
+
+        assert_eq!(result, Error::NotEnoughIngredient);
+        assert_eq!(containers.get_quantity_of(&Ingredient::Coffee), Ok(100));
+        assert_eq!(containers.get_quantity_of(&Ingredient::Cocoa), Ok(100));
+        assert_eq!(
+            containers.get_missing(&get_order(10, 500)),
+            Ok(vec![Ingredient::Cocoa])
+        );
+    }
+
+    #[test]
+    fn test03_concurrent_reservations_dont_lose_ingredients() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("cocoa".to_string(), 7);
+        let containers = Arc::new(Containers::with_clock(&config, Clock::simulated()));
+
+        let mut handles = Vec::new();
+        for dispenser_id in 0..8 {
+            let containers = containers.clone();
+            handles.push(thread::spawn(move || {
+                let mut order = get_order(10, 1);
+                if dispenser_id % 2 == 0 {
+                    order.reverse();
+                }
+                let mut reserved = 0;
+                for _ in 0..2 {
+                    if containers.reserve(&order, dispenser_id, 0).is_ok() {
+                        reserved += 1;
+                    }
+                }
+                reserved
+            }));
+        }
+
+        let mut reserved = 0;
+        for handle in handles {
+            reserved += handle.join().expect("Error when joining");
+        }
+        let coffee = containers
+            .get_quantity_of(&Ingredient::Coffee)
+            .expect("Error when reading coffee");
+        let cocoa = containers
+            .get_quantity_of(&Ingredient::Cocoa)
+            .expect("Error when reading cocoa");
+        assert_eq!(reserved, 7);
+        assert_eq!(coffee, 100 - 10 * reserved);
+        assert_eq!(cocoa, 7 - reserved);
+    }
+
+    #[test]
+    fn test04_every_dispense_and_replenish_is_in_the_audit_log() {
+        let containers =
+            Containers::with_clock(&CoffeeMakerConfig::new(0, 100, 50), Clock::simulated());
+        containers
+            .reserve(&get_order(10, 5), 2, 0)
+            .expect("There are enough ingredients");
+        containers
+            .clone()
+            .replenish_ingredient(&Ingredient::Coffee, 50, 0)
+            .expect("Error when replenishing");
+
+        let log = containers
+            .get_audit_log()
+            .expect("Error when reading the log");
+        let entries = log.get_entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].timestamp_ms, 0);
+        assert_eq!(entries[0].actor, AuditActor::Dispenser(2));
+        assert_eq!(entries[2].ingredient, Ingredient::Coffee);
+        assert_eq!(entries[2].timestamp_ms, 2000);
+        assert_eq!(entries[2].actor, AuditActor::IngredientHandler);
+        assert_eq!(entries[2].operation, AuditOperation::Replenish);
+        assert_eq!(entries[2].amount, 10);
+        assert_eq!((entries[2].before, entries[2].after), (90, 100));
+    }
+
+    #[test]
+    fn test05_concurrent_reservations_of_atomic_containers_dont_lose_ingredients() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("cocoa".to_string(), 7);
+        config.container_backend = ContainerBackend::Atomic;
+        let containers = Arc::new(Containers::with_clock(&config, Clock::simulated()));
+
+        let mut handles = Vec::new();
+        for dispenser_id in 0..8 {
+            let containers = containers.clone();
+            handles.push(thread::spawn(move || {
+                (0..2)
+                    .filter(|_| {
+                        containers
+                            .reserve(&get_order(10, 1), dispenser_id, 0)
+                            .is_ok()
+                    })
+                    .count() as u32
+            }));
+        }
+
+        let mut reserved = 0;
+        for handle in handles {
+            reserved += handle.join().expect("Error when joining");
+        }
+        assert_eq!(reserved, 7);
+        assert_eq!(
+            containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(100 - 10 * reserved)
+        );
+        assert_eq!(containers.get_quantity_of(&Ingredient::Cocoa), Ok(0));
+        assert_eq!(
+            Containers::clone(&containers).replenish_ingredient(&Ingredient::Coffee, 50, 0),
+            Ok(50)
+        );
+        let snapshot = containers.get_snapshot().expect("Error when reading");
+        assert_eq!(snapshot[&Ingredient::Coffee].quantity, 80);
+        assert_eq!(snapshot[&Ingredient::Coffee].replenishments, 1);
+        assert_eq!(
+            containers
+                .get_audit_log()
+                .map(|log| log.get_entries().len()),
+            Ok(0)
+        );
     }
 }
@@ -0,0 +1,169 @@
+use std::{
+    io::BufRead,
+    sync::mpsc::{Receiver, Sender},
+};
+
+use serde::Deserialize;
+
+use crate::{coffee_maker::CoffeeMaker, errors::Error};
+
+/// A command of an operator to the coffee makers while they are making orders.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    Restock {
+        coffee_maker_id: u32,
+        ingredient: String,
+        amount: u32,
+    },
+}
+
+impl Command {
+    /// Converts the command from a json string if it can, returns an error if not.
+    pub fn deserialize(command: &str) -> Result<Command, Error> {
+        match serde_json::from_str::<Command>(command) {
+            Ok(command) => Ok(command),
+            Err(_) => Err(Error::WrongCommandFormat(command.to_owned())),
+        }
+    }
+
+    /// Applies the command to the coffee maker it is for.
+    /// Returns an error if the coffee maker or the ingredient is unknown.
+    pub fn apply(&self, coffee_makers: &[CoffeeMaker]) -> Result<(), Error> {
+        match self {
+            Command::Restock {
+                coffee_maker_id,
+                ingredient,
+                amount,
+            } => {
+                let Some(coffee_maker) = coffee_makers
+                    .iter()
+                    .find(|coffee_maker| coffee_maker.id == *coffee_maker_id)
+                else {
+                    return Err(Error::UnknownCoffeeMaker(*coffee_maker_id));
+                };
+                let ingredient = coffee_maker.registry.get(ingredient)?;
+                coffee_maker.restock(&ingredient, *amount)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the commands of the reader, one json command per line, and sends them through
+/// the channel until the reader or the channel is closed. The empty lines are skipped and
+/// the wrong ones are shown and skipped.
+pub fn read_commands(reader: impl BufRead, commands: Sender<Command>) -> Result<(), Error> {
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            return Err(Error::CantReadFile);
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::deserialize(&line) {
+            Ok(command) => {
+                if commands.send(command).is_err() {
+                    break;
+                }
+            }
+            Err(err) => println!("[CONTROL]: {:?}", err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies every command received through the channel to the coffee makers until the
+/// channel is closed. The commands that can't be applied are shown and skipped.
+pub fn handle_commands(commands: Receiver<Command>, coffee_makers: Vec<CoffeeMaker>) {
+    for command in commands {
+        println!("[CONTROL]: APPLYING {:?}", command);
+        if let Err(err) = command.apply(&coffee_makers) {
+            println!("[CONTROL]: {:?} WHEN APPLYING {:?}", err, command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::mpsc, thread};
+
+    use crate::{
+        clock::Clock,
+        coffee_maker::CoffeeMaker,
+        config::CoffeeMakerConfig,
+        control::{handle_commands, read_commands, Command},
+        errors::Error,
+        ingredients::Ingredient,
+    };
+
+    fn get_coffee_maker(id: u32) -> CoffeeMaker {
+        let mut config = CoffeeMakerConfig::new(id, 0, 50);
+        for ingredient in ["cocoa", "milk"] {
+            config.capacities.insert(ingredient.to_string(), 100);
+        }
+        CoffeeMaker::with_clock(config, Clock::simulated())
+    }
+
+    #[test]
+    fn test01_read_and_apply_the_commands_of_a_reader() {
+        let coffee_makers = vec![get_coffee_maker(0), get_coffee_maker(1)];
+        let lines = "{\"command\": \"restock\", \"coffee_maker_id\": 1, \"ingredient\": \"cocoa\", \"amount\": 30}\n\nnot a command\n{\"command\": \"restock\", \"coffee_maker_id\": 1, \"ingredient\": \"milk\", \"amount\": 20}\n";
+        let (sender, receiver) = mpsc::channel();
+        let handler_coffee_makers = coffee_makers.clone();
+        let handler = thread::spawn(move || handle_commands(receiver, handler_coffee_makers));
+
+        read_commands(Cursor::new(lines), sender).expect("Error when reading the commands");
+        handler.join().expect("Error when joining");
+
+        assert_eq!(
+            coffee_makers[1]
+                .containers
+                .get_quantity_of(&Ingredient::Cocoa),
+            Ok(30)
+        );
+        assert_eq!(
+            coffee_makers[1]
+                .containers
+                .get_quantity_of(&Ingredient::Milk),
+            Ok(20)
+        );
+        assert_eq!(
+            coffee_makers[0]
+                .containers
+                .get_quantity_of(&Ingredient::Cocoa),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test02_a_command_for_an_unknown_coffee_maker_or_ingredient_returns_an_error() {
+        let coffee_makers = vec![get_coffee_maker(0)];
+        let unknown_coffee_maker = Command::Restock {
+            coffee_maker_id: 3,
+            ingredient: "cocoa".to_string(),
+            amount: 10,
+        };
+        let unknown_ingredient = Command::deserialize(
+            "{\"command\": \"restock\", \"coffee_maker_id\": 0, \"ingredient\": \"tea\", \"amount\": 10}",
+        )
+        .expect("The command is valid");
+
+        assert_eq!(
+            unknown_coffee_maker.apply(&coffee_makers),
+            Err(Error::UnknownCoffeeMaker(3))
+        );
+        assert_eq!(
+            unknown_ingredient.apply(&coffee_makers),
+            Err(Error::UnknownIngredient("tea".to_string()))
+        );
+        assert_eq!(
+            Command::deserialize("{\"command\": \"stop\"}"),
+            Err(Error::WrongCommandFormat(
+                "{\"command\": \"stop\"}".to_string()
+            ))
+        );
+    }
+}
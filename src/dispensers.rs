@@ -1,21 +1,14 @@
 pub mod dispenser {
-    use std::{
-        collections::HashMap,
-        sync::{Arc, Condvar, Mutex},
-    };
+    use std::sync::{Arc, Condvar, Mutex};
 
     use crate::{
-        coffee_maker::CoffeeMaker, errors::Error, orders::Order,
-        orders_handler::order_handler::notify_to_replenish_ingredient,
+        coffee_maker::CoffeeMaker,
+        errors::Error,
+        ingredients::Ingredient,
+        orders::Order,
+        orders_handler::order_handler::{notify_to_replenish_ingredient, wait_for_replenish},
     };
 
-    const COFFEE: &str = "coffee";
-    const HOT_WATER: &str = "hot_water";
-    const COCOA: &str = "cocoa";
-    const FOAM: &str = "foam";
-
-    const INGREDIENTS: [&str; 4] = [COFFEE, HOT_WATER, COCOA, FOAM];
-
     /// Increments the total num of orders processed and notifies it.
     pub fn notify_one_order_processed(
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
@@ -37,67 +30,137 @@ pub mod dispenser {
         Ok(())
     }
 
-    fn convert_to_hash(order: Order) -> HashMap<String, u32> {
-        let mut hash_order: HashMap<String, u32> = HashMap::new();
-        hash_order.insert(COFFEE.to_owned(), order.coffee);
-        hash_order.insert(FOAM.to_owned(), order.foam);
-        hash_order.insert(HOT_WATER.to_owned(), order.water);
-        hash_order.insert(COCOA.to_owned(), order.cocoa);
+    /// Notifies the ingredient handler of its coffee machine to replenish the missing
+    /// ingredients and waits until they were replenished. The wait is counted.
+    fn replenish_missing(
+        missing: &[Ingredient],
+        coffee_maker: &CoffeeMaker,
+        has_to_replenish: &Arc<(Mutex<Vec<bool>>, Condvar)>,
+    ) -> Result<(), Error> {
+        let indexes = missing
+            .iter()
+            .map(|ingredient| coffee_maker.handler.get_index(ingredient))
+            .collect::<Result<Vec<u32>, Error>>()?;
+        coffee_maker.handler.count_wait();
+        for idx in &indexes {
+            notify_to_replenish_ingredient(has_to_replenish.clone(), *idx);
+        }
+        for idx in indexes {
+            wait_for_replenish(
+                has_to_replenish.clone(),
+                idx,
+                &coffee_maker.containers.clock,
+            );
+        }
 
-        hash_order
+        Ok(())
     }
 
-    /// Gets all the ingredients of the order.
+    /// Notifies the ingredient handler of its coffee machine to replenish in the background
+    /// the ingredients received that dropped below their low-water mark, without waiting.
+    fn replenish_below_low_water_mark(
+        ingredients: &[(Ingredient, u32)],
+        coffee_maker: &CoffeeMaker,
+        dispenser_id: u32,
+        has_to_replenish: &Arc<(Mutex<Vec<bool>>, Condvar)>,
+    ) -> Result<(), Error> {
+        for (ingredient, _) in ingredients {
+            if coffee_maker.handler.can_replenish(ingredient)
+                && coffee_maker.handler.is_below_low_water_mark(ingredient)?
+            {
+                println!(
+                    "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: {:?} IS BELOW ITS LOW-WATER MARK",
+                    dispenser_id,
+                    coffee_maker.id,
+                    ingredient.name()
+                );
+                notify_to_replenish_ingredient(
+                    has_to_replenish.clone(),
+                    coffee_maker.handler.get_index(ingredient)?,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets all the ingredients of the order from their containers at once, or none of
+    /// them if any is missing.
     /// Also calls to the ingredient handler of its coffee machine to replenish
-    /// ingredients if its necessary.
+    /// the missing ingredients if all of them can be replenished, and tries again once
+    /// they were replenished. If they can't be replenished and its coffee machine waits
+    /// for restocks, it tries again every time an operator restocks a container until
+    /// the restock wait passes without restocks. Once it got them, the ingredients that
+    /// dropped below their low-water mark are replenished in the background and the
+    /// alerts of their containers are checked.
     pub fn make_order(
         order: Order,
         coffee_maker: CoffeeMaker,
         dispenser_id: u32,
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
+        has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
     ) -> Result<(), Error> {
-        let hash_order: HashMap<String, u32> = convert_to_hash(order);
-        for ingredient in INGREDIENTS {
-            let has_to_replenish = has_to_replenish.clone();
-            match coffee_maker.containers.clone().get_ingredient(
-                &ingredient.to_owned(),
-                hash_order[ingredient],
-                Some(dispenser_id),
-                coffee_maker.id,
-            ) {
-                Ok(_) => {
-                    println!(
-                        "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: GOT {:?}",
-                        dispenser_id, coffee_maker.id, ingredient
-                    );
-                }
-                Err(err) => match err {
-                    Error::NotEnoughIngredient => {
-                        if ingredient != COCOA {
-                            let idx = coffee_maker
-                                .clone()
-                                .handler
-                                .get_index(ingredient.to_owned());
-
-                            notify_to_replenish_ingredient(has_to_replenish.clone(), idx);
-                            println!(
-                                "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: TRY GET {:?} AGAIN",
-                                dispenser_id, coffee_maker.id, ingredient
-                            );
-                            coffee_maker.containers.clone().get_ingredient(
-                                &ingredient.to_owned(),
-                                hash_order[ingredient],
-                                Some(dispenser_id),
-                                coffee_maker.id,
-                            )?
-                        }
-                    }
-                    _ => return Err(err),
-                },
+        let ingredients = order.get_containers(&coffee_maker.registry)?;
+        let mut replenished = false;
+        loop {
+            let restocks = coffee_maker.containers.get_restocks()?;
+            match coffee_maker
+                .containers
+                .reserve(&ingredients, dispenser_id, coffee_maker.id)
+            {
+                Ok(_) => break,
+                Err(Error::NotEnoughIngredient) => {}
+                Err(err) => return Err(err),
             };
+
+            let missing = coffee_maker.containers.get_missing(&ingredients)?;
+            if !replenished
+                && missing
+                    .iter()
+                    .all(|ingredient| coffee_maker.handler.can_replenish(ingredient))
+            {
+                replenish_missing(&missing, &coffee_maker, &has_to_replenish)?;
+                replenished = true;
+            } else {
+                let Some(restock_wait) = coffee_maker.get_restock_wait() else {
+                    return Err(Error::NotEnoughIngredient);
+                };
+                println!(
+                    "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: WAITING FOR A RESTOCK OF {:?}",
+                    dispenser_id,
+                    coffee_maker.id,
+                    missing.iter().map(Ingredient::name).collect::<Vec<&str>>()
+                );
+                if !coffee_maker
+                    .containers
+                    .wait_for_restock(restocks, restock_wait)?
+                {
+                    return Err(Error::NotEnoughIngredient);
+                }
+                replenished = false;
+            }
+            println!(
+                "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: TRY GET {:?} AGAIN",
+                dispenser_id,
+                coffee_maker.id,
+                missing.iter().map(Ingredient::name).collect::<Vec<&str>>()
+            );
         }
 
+        replenish_below_low_water_mark(
+            &ingredients,
+            &coffee_maker,
+            dispenser_id,
+            &has_to_replenish,
+        )?;
+        let taken: Vec<Ingredient> = ingredients
+            .iter()
+            .map(|(ingredient, _)| ingredient.clone())
+            .collect();
+        coffee_maker
+            .handler
+            .notify_to_alert_ingredients(has_to_alert, &taken);
         notify_one_order_processed(orders_processed, dispenser_id, coffee_maker.id)?;
 
         Ok(())
@@ -1,8 +1,32 @@
+use crate::{units::Unit, validation::ValidationError};
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     NotFileInput,
     FileNotFound,
+    CantReadFile,
+    ConfigFileNotFound,
+    CantReadConfigFile,
+    WrongConfigFormat,
+    SnapshotNotFound,
+    WrongSnapshotFormat,
+    ControlFileNotFound,
     WrongFileFormat,
+    WrongCsvRow(usize),
+    InvalidOrders(Vec<ValidationError>),
+    UnknownFormat(String),
+    WrongArrivalRate(String),
+    WrongClock(String),
+    WrongCommandFormat(String),
+    WrongAlertSinkUrl(String),
+    WrongRecipesFormat,
+    UnknownRecipe(String),
+    UnknownCoffeeMaker(u32),
+    UnknownIngredient(String),
+    UnknownUnit(String),
+    WrongQuantity(String),
+    CantConvertUnit(String, Unit),
+    NotReplenishable(String),
     NotEnoughIngredient,
     NoMoreOrders,
     CantReadOrdersLock,
@@ -10,4 +34,10 @@ pub enum Error {
     CantWriteContainerLock,
     CantReadContainerLock,
     CantHaveOrdersProcessedLock,
+    CantHaveReportLock,
+    CantHaveRestocksLock,
+    CantWriteReport,
+    CantWriteSnapshot,
+    CantWriteAuditLog,
+    CantSendAlert(String),
 }
@@ -0,0 +1,144 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{clock::Clock, errors::Error, orders::Order, orders_queue::OrdersQueue};
+
+/// Generator of the random times between the arrivals of the customers.
+#[derive(Debug, Clone)]
+pub struct ArrivalGenerator {
+    state: u64,
+}
+
+impl ArrivalGenerator {
+    /// Creates a new [`ArrivalGenerator`] that always generates the same times for the
+    /// same seed.
+    pub fn new(seed: u64) -> ArrivalGenerator {
+        ArrivalGenerator { state: seed.max(1) }
+    }
+
+    /// Creates a new [`ArrivalGenerator`] with a seed taken from the current time.
+    pub fn from_time() -> ArrivalGenerator {
+        let seed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(now) => now.as_nanos() as u64,
+            Err(_) => 1,
+        };
+        ArrivalGenerator::new(seed)
+    }
+
+    /// Returns a random number between 0 (excluded) and 1 (included).
+    fn next_unit(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        ((self.state >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns the milliseconds until the next arrival of customers that arrive at the rate
+    /// of orders per second received, following a Poisson process.
+    pub fn next_gap_ms(&mut self, rate: f64) -> u64 {
+        (-self.next_unit().ln() / rate * 1000.0) as u64
+    }
+}
+
+/// Gives to the orders without arrival time the arrival times of customers that arrive
+/// at the rate of orders per second received, in the order they are.
+pub fn set_poisson_arrivals(orders: &mut [Order], rate: f64, generator: &mut ArrivalGenerator) {
+    let mut arrival_ms = 0;
+    for order in orders.iter_mut().filter(|order| order.arrival_ms.is_none()) {
+        arrival_ms += generator.next_gap_ms(rate);
+        order.arrival_ms = Some(arrival_ms);
+    }
+}
+
+/// Returns true if any of the orders has an arrival time.
+pub fn has_arrivals(orders: &[Order]) -> bool {
+    orders.iter().any(|order| order.arrival_ms.is_some())
+}
+
+/// Pushes every order to the queue at its arrival time, measured from the start of the
+/// clock received, in order of arrival. The orders without arrival time are pushed at the
+/// start. The queue is closed once all the orders were pushed.
+pub fn feed_orders(
+    mut orders: Vec<Order>,
+    queue: Arc<OrdersQueue>,
+    clock: Clock,
+) -> Result<(), Error> {
+    orders.sort_by_key(|order| order.arrival_ms.unwrap_or_default());
+    for order in orders {
+        clock.advance_to_ms(order.arrival_ms.unwrap_or_default());
+        println!(
+            "[FEEDER]: ORDER {:?} ARRIVES AT {:?} MS",
+            order.id,
+            clock.now_ms()
+        );
+        if let Err(err) = queue.push(order) {
+            queue.close()?;
+            return Err(err);
+        }
+    }
+
+    println!("[FEEDER]: NO MORE ORDERS ARRIVING");
+    queue.close()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        clock::Clock,
+        feeder::{feed_orders, has_arrivals, set_poisson_arrivals, ArrivalGenerator},
+        orders::Order,
+        orders_queue::OrdersQueue,
+    };
+
+    #[test]
+    fn test01_the_poisson_arrivals_keep_the_times_of_the_orders_that_have_one() {
+        let mut orders = vec![
+            Order::new(1, 1, 1, 1),
+            Order::new(1, 1, 1, 1).with_arrival_ms(5),
+            Order::new(1, 1, 1, 1),
+            Order::new(1, 1, 1, 1),
+        ];
+        assert!(has_arrivals(&orders));
+        set_poisson_arrivals(&mut orders, 10.0, &mut ArrivalGenerator::new(7));
+
+        let arrivals: Vec<u64> = orders
+            .iter()
+            .map(|order| order.arrival_ms.expect("Every order has an arrival time"))
+            .collect();
+        assert_eq!(arrivals[1], 5);
+        assert!(arrivals[0] <= arrivals[2] && arrivals[2] <= arrivals[3]);
+    }
+
+    #[test]
+    fn test02_the_poisson_arrivals_have_the_rate_received() {
+        let mut generator = ArrivalGenerator::new(42);
+        let total_ms: u64 = (0..10000).map(|_| generator.next_gap_ms(100.0)).sum();
+
+        let average_ms = total_ms as f64 / 10000.0;
+        assert!((average_ms - 10.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test03_feed_the_orders_in_order_of_arrival() {
+        let orders = vec![
+            Order::new(1, 1, 1, 1).with_id(0).with_arrival_ms(60),
+            Order::new(1, 1, 1, 1).with_id(1),
+            Order::new(1, 1, 1, 1).with_id(2).with_arrival_ms(30),
+        ];
+        let queue = Arc::new(OrdersQueue::new());
+        let clock = Clock::real();
+        feed_orders(orders, queue.clone(), clock.clone()).expect("Error when feeding the orders");
+
+        assert!(clock.now_ms() >= 60);
+        assert_eq!(queue.len(), 3);
+        let mut ids = Vec::new();
+        while let Ok(order) = queue.pop() {
+            ids.push(order.id);
+        }
+        assert_eq!(ids, vec![1, 2, 0]);
+    }
+}
@@ -1,173 +1,236 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 
+use crate::alerts::AlertThresholds;
+use crate::config::CoffeeMakerConfig;
 use crate::containers::Containers;
 use crate::errors::Error;
-
-const COFFEE: &str = "coffee";
-const HOT_WATER: &str = "hot_water";
-const FOAM: &str = "foam";
-const GRAIN_COFFEE: &str = "grain_coffee";
-const MILK: &str = "milk";
-const COLD_WATER: &str = "cold_water";
-const COCOA: &str = "cocoa";
-
-const IDX_COFFEE: u32 = 0;
-const IDX_WATER: u32 = 1;
-const IDX_FOAM: u32 = 2;
-
-const RESOURCES_TO_ALARM: [&str; 3] = [GRAIN_COFFEE, MILK, COCOA];
-const VALUE_TO_ALERT: u32 = 50;
-
-const IDX_GRAIN_COFFEE: u32 = 0;
-const IDX_MILK: u32 = 1;
-const IDX_COCOA: u32 = 2;
+use crate::ingredients::{get_yield, Ingredient, ReplenishRule};
 
 #[derive(Clone)]
 pub struct IHandler {
     coffee_maker_id: u32,
     containers: Containers,
-    values: HashMap<String, (String, u32, u32)>,
+    rules: Vec<ReplenishRule>,
+    low_water_marks: HashMap<Ingredient, u32>,
+    alert_levels: AlertThresholds,
+    alerted: Vec<Ingredient>,
+    waits: Arc<AtomicU32>,
+    replenished: Arc<HashMap<Ingredient, AtomicU32>>,
 }
 
 impl IHandler {
-    /// Creates a new [`IHandler`].
-    pub fn new(containers_list: Containers, id: u32, replenish_value: u32) -> IHandler {
-        let mut ingredients = HashMap::new();
-        ingredients.insert(
-            COFFEE.to_owned(),
-            (GRAIN_COFFEE.to_owned(), replenish_value, IDX_COFFEE),
-        );
-        ingredients.insert(
-            FOAM.to_owned(),
-            (MILK.to_owned(), replenish_value, IDX_FOAM),
-        );
-        ingredients.insert(
-            HOT_WATER.to_owned(),
-            (COLD_WATER.to_owned(), replenish_value, IDX_WATER),
-        );
+    /// Creates a new [`IHandler`] with the replenish rules, the low-water marks and the
+    /// alert levels of the configuration. The alert flags of the containers are in the order
+    /// of their ingredients.
+    pub fn new(containers_list: Containers, config: &CoffeeMakerConfig) -> IHandler {
+        let rules = config.get_replenish_rules();
+        let mut low_water_marks = HashMap::new();
+        let mut replenished = HashMap::new();
+        for rule in &rules {
+            low_water_marks.insert(rule.target.clone(), config.get_low_water_mark(&rule.target));
+            replenished.insert(rule.target.clone(), AtomicU32::new(0));
+        }
 
         IHandler {
-            coffee_maker_id: id,
+            coffee_maker_id: config.id,
             containers: containers_list,
-            values: ingredients,
+            rules,
+            low_water_marks,
+            alert_levels: config.alert_levels,
+            alerted: config.get_ingredients(),
+            waits: Arc::new(AtomicU32::new(0)),
+            replenished: Arc::new(replenished),
         }
     }
 
-    /// Notifies to alert an ingredient.
-    pub fn notify_to_alert_ingredient(
-        self,
+    /// Returns its replenish rules, in the order of their replenish flags.
+    pub fn get_rules(&self) -> &[ReplenishRule] {
+        &self.rules
+    }
+
+    /// Notifies to check the alerts of the containers of the ingredients received.
+    pub fn notify_to_alert_ingredients(
+        &self,
         has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
-        idx: u32,
+        ingredients: &[Ingredient],
     ) {
         let (has_to_alert_lock, condvar) = &*has_to_alert;
         if let Ok(mut has_to_alert) = has_to_alert_lock.lock() {
-            if !has_to_alert[idx as usize] {
-                has_to_alert[idx as usize] = true;
+            for ingredient in ingredients {
+                let idx = self.alerted.iter().position(|other| other == ingredient);
+                if let Some(flag) = idx.and_then(|idx| has_to_alert.get_mut(idx)) {
+                    *flag = true;
+                }
             }
         }
         condvar.notify_all();
     }
 
-    fn get_resources_indexes(self) -> HashMap<String, u32> {
-        let mut values = HashMap::new();
-        values.insert(GRAIN_COFFEE.to_owned(), IDX_GRAIN_COFFEE);
-        values.insert(MILK.to_owned(), IDX_MILK);
-        values.insert(COCOA.to_owned(), IDX_COCOA);
-
-        values
-    }
-
-    pub fn check_for_resources(self, has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>) {
-        println!(
-            "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: CHECKING RESOURCES",
-            self.coffee_maker_id
-        );
-        let indexes = self.clone().get_resources_indexes();
-
-        for resource in RESOURCES_TO_ALARM {
-            println!(
-                "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: CHECKING FOR {}",
-                self.coffee_maker_id, resource
-            );
-            if let Ok(quantity) = self
-                .clone()
-                .containers
-                .get_quantity_of(&resource.to_owned())
-            {
-                println!(
-                    "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {} HAS {}",
-                    self.coffee_maker_id, resource, quantity
-                );
-                if quantity == VALUE_TO_ALERT {
-                    println!(
-                        "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {} HAS TO BE ALERTED",
-                        self.coffee_maker_id, resource
-                    );
-                    self.clone()
-                        .notify_to_alert_ingredient(has_to_alert.clone(), indexes[resource]);
-                }
+    /// Returns the ingredients whose container is at or below one of the alert levels.
+    pub fn get_pending_alerts(&self) -> Result<Vec<Ingredient>, Error> {
+        let mut pending_alerts = Vec::new();
+        for ingredient in &self.alerted {
+            let quantity = self.containers.get_quantity_of(ingredient)?;
+            let capacity = self.containers.get_capacity_of(ingredient)?;
+            if self.alert_levels.get_level(quantity, capacity).is_some() {
+                pending_alerts.push(ingredient.clone());
             }
         }
+
+        Ok(pending_alerts)
     }
 
-    /// Returns true if there is not enough ingredient, false if there is.
-    fn has_to_replenish(self, ingredient: &String) -> Result<bool, Error> {
+    /// Returns true if the ingredient is empty or below its low-water mark, false if not.
+    fn has_to_replenish(self, ingredient: &Ingredient) -> Result<bool, Error> {
         let current_quantity = self.containers.get_quantity_of(ingredient)?;
-        let replenish = current_quantity == 0;
+        let replenish = current_quantity == 0 || self.is_below_low_water_mark(ingredient)?;
         println!(
             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: ¿HAS TO REPLENISH {:?}? {:?}",
-            self.coffee_maker_id, ingredient, replenish
+            self.coffee_maker_id,
+            ingredient.name(),
+            replenish
         );
 
         Ok(replenish)
     }
 
-    pub fn get_index(self, ingredient: String) -> u32 {
-        self.values[&ingredient].2
+    /// Returns true if the quantity of the ingredient is below its low-water mark.
+    pub fn is_below_low_water_mark(&self, ingredient: &Ingredient) -> Result<bool, Error> {
+        let low_water_mark = self
+            .low_water_marks
+            .get(ingredient)
+            .copied()
+            .unwrap_or_default();
+
+        Ok(self.containers.get_quantity_of(ingredient)? < low_water_mark)
+    }
+
+    /// Counts that a dispenser had to wait for an ingredient to be replenished.
+    pub fn count_wait(&self) {
+        self.waits.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns how many times the dispensers had to wait for an ingredient to be
+    /// replenished.
+    pub fn get_waits(&self) -> u32 {
+        self.waits.load(Ordering::SeqCst)
+    }
+
+    /// Returns how much of every ingredient it replenished.
+    pub fn get_replenished(&self) -> HashMap<Ingredient, u32> {
+        self.replenished
+            .iter()
+            .map(|(ingredient, added)| (ingredient.clone(), added.load(Ordering::SeqCst)))
+            .collect()
+    }
+
+    /// Returns true if the ingredient has a resource to be replenished from.
+    pub fn can_replenish(&self, ingredient: &Ingredient) -> bool {
+        self.rules.iter().any(|rule| rule.target == *ingredient)
+    }
+
+    /// Returns the replenish rule of an ingredient, or an error if it has no resource to
+    /// be replenished from.
+    fn get_rule_of(&self, ingredient: &Ingredient) -> Result<&ReplenishRule, Error> {
+        match self.rules.iter().find(|rule| rule.target == *ingredient) {
+            Some(rule) => Ok(rule),
+            None => Err(Error::NotReplenishable(ingredient.name().to_owned())),
+        }
+    }
+
+    /// Returns the index of the flag to replenish the ingredient.
+    pub fn get_index(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        match self
+            .rules
+            .iter()
+            .position(|rule| rule.target == *ingredient)
+        {
+            Some(idx) => Ok(idx as u32),
+            None => Err(Error::NotReplenishable(ingredient.name().to_owned())),
+        }
+    }
+
+    /// Returns the quantity of the resource to replenish the ingredient with: its replenish
+    /// value or, if what it yields doesn't fit in its container, the greatest quantity whose
+    /// yield fits in the free space of the container.
+    fn get_refill_amount(&self, ingredient: &Ingredient) -> Result<u32, Error> {
+        let free_space = self.containers.get_free_space_of(ingredient)?;
+        let rule = self.get_rule_of(ingredient)?;
+        let mut amount = rule
+            .amount
+            .min((free_space as f64 / rule.ratio).floor() as u32);
+        while amount > 0 && get_yield(amount, rule.ratio) > free_space {
+            amount -= 1;
+        }
+
+        Ok(amount)
+    }
+
+    /// Returns the quantity of the ingredient obtained from the quantity of its resource
+    /// received.
+    pub fn get_yield_of(&self, ingredient: &Ingredient, amount: u32) -> Result<u32, Error> {
+        Ok(get_yield(amount, self.get_rule_of(ingredient)?.ratio))
     }
 
     /// Decrease the quantity of the resource of the ingredient.
-    fn get_ingredient(&mut self, ingredient: &String) -> Result<(), Error> {
-        let resource = &self.values[ingredient].0;
+    fn get_ingredient(&mut self, ingredient: &Ingredient, amount: u32) -> Result<(), Error> {
+        let resource = &self.get_rule_of(ingredient)?.source;
         println!(
             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: EXTRACTING FROM {:?} CONTAINER",
-            self.coffee_maker_id, resource
-        );
-        self.containers.clone().get_ingredient(
-            resource,
-            self.values[ingredient].1,
-            None,
             self.coffee_maker_id,
-        )?;
+            resource.name()
+        );
+        self.containers
+            .clone()
+            .get_ingredient(resource, amount, None, self.coffee_maker_id)?;
 
         Ok(())
     }
 
     /// Increments the quantity of the ingredient.
-    pub fn replenish_ingredient(&mut self, ingredient: &String) -> Result<(), Error> {
+    /// Returns the quantity that was added.
+    pub fn replenish_ingredient(
+        &mut self,
+        ingredient: &Ingredient,
+        amount: u32,
+    ) -> Result<u32, Error> {
+        let mut added = 0;
         if self.clone().has_to_replenish(ingredient)? {
             println!(
                 "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: GETTING MORE {:?} ",
-                self.coffee_maker_id, ingredient
+                self.coffee_maker_id,
+                ingredient.name()
             );
-            self.containers.clone().replenish_ingredient(
+            added = self.containers.clone().replenish_ingredient(
                 ingredient,
-                self.values[ingredient].1,
+                amount,
                 self.coffee_maker_id,
             )?;
         }
 
-        Ok(())
+        Ok(added)
     }
 
     /// Performs the increment and decrement of the quantities of the ingredient and its
-    /// resource.
-    /// If the ingredient is water, it only performs the increment of it.
-    pub fn replenish(&mut self, ingredient: &String) -> Result<(), Error> {
+    /// resource, taking from the resource only what yields a quantity that fits in the
+    /// container of the ingredient.
+    pub fn replenish(&mut self, ingredient: &Ingredient) -> Result<(), Error> {
         if self.clone().has_to_replenish(ingredient)? {
-            self.get_ingredient(ingredient)?;
-            self.replenish_ingredient(ingredient)?;
+            let amount = self.get_refill_amount(ingredient)?;
+            self.get_ingredient(ingredient, amount)?;
+            let added =
+                self.replenish_ingredient(ingredient, self.get_yield_of(ingredient, amount)?)?;
+            if let Some(replenished) = self.replenished.get(ingredient) {
+                replenished.fetch_add(added, Ordering::SeqCst);
+            }
+            println!(
+                "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: ADDED {:?} OF {:?}",
+                self.coffee_maker_id,
+                added,
+                ingredient.name()
+            );
         }
 
         Ok(())
@@ -176,7 +239,7 @@ impl IHandler {
     /// Performs the replenishment of the ingredient received when its necessary.
     pub fn do_replenish(
         &mut self,
-        ingredient: &String,
+        ingredient: &Ingredient,
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
         handler_is_awake: Arc<(Mutex<Vec<bool>>, Condvar)>,
         idx: usize,
@@ -192,30 +255,37 @@ impl IHandler {
         if let Ok(has_to_replenish) = has_to_replenish_lock.lock() {
             println!(
                 "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: WAITING TO REPLENISH {:?}",
-                self.coffee_maker_id, ingredient
+                self.coffee_maker_id,
+                ingredient.name()
             );
             if let Ok(mut has_to_replenish) = condvar.wait_while(has_to_replenish, |v| !v[idx]) {
                 println!(
                     "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: START REPLENISHING {:?}",
-                    self.coffee_maker_id, ingredient
+                    self.coffee_maker_id,
+                    ingredient.name()
                 );
                 match self.replenish(ingredient) {
                     Ok(_) => {
                         has_to_replenish[idx] = false;
                         println!(
                             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {:?} REPLENISHED ",
-                            self.coffee_maker_id, ingredient
+                            self.coffee_maker_id,
+                            ingredient.name()
                         );
                     }
                     Err(err) => {
                         has_to_replenish[idx] = false;
                         println!(
                             "[INGREDIENT HANDLER] OF [COFFEE MAKER {:?}]: {:?} WHEN REPLENISHING {:?}",
-                            self.coffee_maker_id, err, ingredient
+                            self.coffee_maker_id, err, ingredient.name()
                         );
                     }
                 }
-                self.clone().check_for_resources(has_to_alert);
+                let mut alerted = vec![ingredient.clone()];
+                if let Ok(rule) = self.get_rule_of(ingredient) {
+                    alerted.push(rule.source.clone());
+                }
+                self.notify_to_alert_ingredients(has_to_alert, &alerted);
             }
             condvar.notify_all();
         }
@@ -227,9 +297,11 @@ impl IHandler {
 #[cfg(test)]
 mod tests {
     use crate::{
+        clock::Clock,
+        config::{CoffeeMakerConfig, ReplenishRuleConfig},
         containers::Containers,
         errors::Error,
-        ingredient_handler::{COFFEE, FOAM, HOT_WATER},
+        ingredients::Ingredient,
     };
 
     use super::IHandler;
@@ -237,45 +309,43 @@ mod tests {
     #[test]
     fn test01_has_to_replenish_and_has_enough_resources_ingredient_so_quantity_of_ingredients_is_updated(
     ) {
-        let containers = Containers::new(0);
-        if let Ok(mut grain_coffee_container) = containers.all["grain_coffee"].write() {
-            grain_coffee_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
+        let mut config = CoffeeMakerConfig::new(0, 0, 10);
+        for resource in ["grain_coffee", "milk", "cold_water"] {
+            config.capacities.insert(resource.to_owned(), 1050);
         }
-        if let Ok(mut milk_container) = containers.all["milk"].write() {
-            milk_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
-        }
-        if let Ok(mut cold_water_container) = containers.all["cold_water"].write() {
-            cold_water_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
-        }
-        let mut handler = IHandler::new(containers, 0, 10);
+        let containers = Containers::with_clock(&config, Clock::simulated());
+        containers.all[&Ingredient::GrainCoffee]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        containers.all[&Ingredient::Milk]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        containers.all[&Ingredient::ColdWater]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        let mut handler = IHandler::new(containers, &config);
 
         handler
-            .replenish(&COFFEE.to_owned())
+            .replenish(&Ingredient::Coffee)
             .expect("Error when replenishing coffee");
         handler
-            .replenish(&HOT_WATER.to_owned())
+            .replenish(&Ingredient::HotWater)
             .expect("Error when replenishing water");
         handler
-            .replenish(&FOAM.to_owned())
+            .replenish(&Ingredient::Foam)
             .expect("Error when replenishing foam");
 
         let coffee_got = handler
             .containers
-            .get_quantity_of(&COFFEE.to_string())
+            .get_quantity_of(&Ingredient::Coffee)
             .expect("Error when locking coffee container");
         let foam_got = handler
             .containers
-            .get_quantity_of(&FOAM.to_string())
+            .get_quantity_of(&Ingredient::Foam)
             .expect("Error when locking foam container");
         let water_got = handler
             .containers
-            .get_quantity_of(&HOT_WATER.to_string())
+            .get_quantity_of(&Ingredient::HotWater)
             .expect("Error when locking water container");
 
         assert_eq!(coffee_got, 10);
@@ -286,41 +356,39 @@ mod tests {
     #[test]
     fn test02_has_to_replenish_and_has_enough_resources_ingredient_so_quantity_of_resources_is_updated(
     ) {
-        let containers = Containers::new(0);
-        if let Ok(mut grain_coffee_container) = containers.all["grain_coffee"].write() {
-            grain_coffee_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
-        }
-        if let Ok(mut milk_container) = containers.all["milk"].write() {
-            milk_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
-        }
-        if let Ok(mut cold_water_container) = containers.all["cold_water"].write() {
-            cold_water_container
-                .replenish(50, 0)
-                .expect("Error when writing container");
+        let mut config = CoffeeMakerConfig::new(0, 0, 10);
+        for resource in ["grain_coffee", "milk", "cold_water"] {
+            config.capacities.insert(resource.to_owned(), 1050);
         }
-        let mut handler = IHandler::new(containers, 0, 10);
+        let containers = Containers::with_clock(&config, Clock::simulated());
+        containers.all[&Ingredient::GrainCoffee]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        containers.all[&Ingredient::Milk]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        containers.all[&Ingredient::ColdWater]
+            .replenish(50, 0, 0)
+            .expect("Error when writing container");
+        let mut handler = IHandler::new(containers, &config);
 
         handler
-            .replenish(&COFFEE.to_owned())
+            .replenish(&Ingredient::Coffee)
             .expect("Error when replenishing coffee");
         handler
-            .replenish(&HOT_WATER.to_owned())
+            .replenish(&Ingredient::HotWater)
             .expect("Error when replenishing water");
         handler
-            .replenish(&FOAM.to_owned())
+            .replenish(&Ingredient::Foam)
             .expect("Error when replenishing foam");
 
         let grain_coffee_got = handler
             .containers
-            .get_quantity_of(&"grain_coffee".to_string())
+            .get_quantity_of(&Ingredient::GrainCoffee)
             .expect("Error when reading coffee container");
         let milk_got = handler
             .containers
-            .get_quantity_of(&"milk".to_string())
+            .get_quantity_of(&Ingredient::Milk)
             .expect("Error when reading foam container");
 
         assert_eq!(grain_coffee_got, 40);
@@ -329,12 +397,14 @@ mod tests {
 
     #[test]
     fn test03_has_to_replenish_but_do_not_has_enough_resource_so_do_not_replenish_them() {
-        let mut handler = IHandler::new(Containers::new(0), 0, 50);
+        let config = CoffeeMakerConfig::new(0, 0, 50);
+        let mut handler =
+            IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
         let coffee_got = handler
-            .replenish(&COFFEE.to_owned())
+            .replenish(&Ingredient::Coffee)
             .expect_err("Error when replenishing coffee");
         let foam_got = handler
-            .replenish(&FOAM.to_owned())
+            .replenish(&Ingredient::Foam)
             .expect_err("Error when replenishing foam container");
         let err_expected = Error::NotEnoughIngredient;
 
@@ -344,41 +414,199 @@ mod tests {
 
     #[test]
     fn test04_quantity_is_zero_so_has_to_replenish() {
-        let handler = IHandler::new(Containers::new(0), 0, 10);
+        let config = CoffeeMakerConfig::new(0, 0, 10);
+        let handler = IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
         let coffee_got = handler
             .clone()
-            .has_to_replenish(&COFFEE.to_owned())
+            .has_to_replenish(&Ingredient::Coffee)
             .expect("Error when replenishing coffee");
         let foam_got = handler
             .clone()
-            .has_to_replenish(&FOAM.to_owned())
+            .has_to_replenish(&Ingredient::Foam)
             .expect("Error when replenishing coffee");
         let water_got = handler
-            .has_to_replenish(&HOT_WATER.to_owned())
+            .has_to_replenish(&Ingredient::HotWater)
             .expect("Error when replenishing coffee");
 
-        assert_eq!(coffee_got, true);
-        assert_eq!(foam_got, true);
-        assert_eq!(water_got, true);
+        assert!(coffee_got);
+        assert!(foam_got);
+        assert!(water_got);
     }
 
     #[test]
     fn test05_quantity_is_ten_so_does_not_has_to_replenish() {
-        let handler = IHandler::new(Containers::new(10), 0, 10);
+        let config = CoffeeMakerConfig::new(0, 10, 10);
+        let handler = IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
         let coffee_got = handler
             .clone()
-            .has_to_replenish(&COFFEE.to_owned())
+            .has_to_replenish(&Ingredient::Coffee)
             .expect("Error when replenishing coffee");
         let foam_got = handler
             .clone()
-            .has_to_replenish(&FOAM.to_owned())
+            .has_to_replenish(&Ingredient::Foam)
             .expect("Error when replenishing coffee");
         let water_got = handler
-            .has_to_replenish(&HOT_WATER.to_owned())
+            .has_to_replenish(&Ingredient::HotWater)
+            .expect("Error when replenishing coffee");
+
+        assert!(!coffee_got);
+        assert!(!foam_got);
+        assert!(!water_got);
+    }
+
+    #[test]
+    fn test06_replenish_only_what_fits_in_the_container() {
+        let mut config = CoffeeMakerConfig::new(0, 0, 50);
+        config.containers.insert("grain_coffee".to_owned(), 100);
+        config.capacities.insert("coffee".to_owned(), 30);
+        let containers = Containers::with_clock(&config, Clock::simulated());
+        let mut handler = IHandler::new(containers, &config);
+
+        handler
+            .replenish(&Ingredient::Coffee)
+            .expect("Error when replenishing coffee");
+
+        let coffee_got = handler
+            .containers
+            .get_quantity_of(&Ingredient::Coffee)
+            .expect("Error when reading coffee container");
+        let grain_coffee_got = handler
+            .containers
+            .get_quantity_of(&Ingredient::GrainCoffee)
+            .expect("Error when reading grain coffee container");
+        assert_eq!(coffee_got, 30);
+        assert_eq!(grain_coffee_got, 70);
+    }
+
+    #[test]
+    fn test07_an_ingredient_without_resource_can_not_be_replenished() {
+        let config = CoffeeMakerConfig::new(0, 0, 10);
+        let handler = IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
+
+        assert!(handler.can_replenish(&Ingredient::Foam));
+        assert_eq!(handler.get_index(&Ingredient::HotWater), Ok(1));
+        assert!(!handler.can_replenish(&Ingredient::Cocoa));
+        assert_eq!(
+            handler.get_index(&Ingredient::Cocoa),
+            Err(Error::NotReplenishable("cocoa".to_string()))
+        );
+    }
+
+    #[test]
+    fn test08_an_ingredient_below_its_low_water_mark_has_to_be_replenished() {
+        let mut config = CoffeeMakerConfig::new(0, 40, 10);
+        config.low_water_marks.insert("foam".to_string(), 50);
+        config.capacities.insert("foam".to_string(), 100);
+        let mut handler =
+            IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
+
+        assert_eq!(handler.is_below_low_water_mark(&Ingredient::Foam), Ok(true));
+        assert_eq!(
+            handler.is_below_low_water_mark(&Ingredient::Coffee),
+            Ok(false)
+        );
+        handler
+            .replenish(&Ingredient::Foam)
+            .expect("Error when replenishing foam");
+        handler
+            .replenish(&Ingredient::Coffee)
+            .expect("Error when replenishing coffee");
+
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Foam),
+            Ok(50)
+        );
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(40)
+        );
+    }
+
+    #[test]
+    fn test09_the_resource_yields_the_ratio_of_the_ingredient_that_fits_in_its_container() {
+        let mut config = CoffeeMakerConfig::new(0, 0, 50);
+        config.containers.insert("grain_coffee".to_owned(), 100);
+        config.containers.insert("milk".to_owned(), 100);
+        config.yield_ratios.insert("coffee".to_owned(), 0.8);
+        config.yield_ratios.insert("foam".to_owned(), 1.5);
+        config.capacities.insert("foam".to_owned(), 60);
+        let mut handler =
+            IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
+
+        handler
+            .replenish(&Ingredient::Coffee)
             .expect("Error when replenishing coffee");
+        handler
+            .replenish(&Ingredient::Foam)
+            .expect("Error when replenishing foam");
+
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(40)
+        );
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::GrainCoffee),
+            Ok(50)
+        );
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Foam),
+            Ok(60)
+        );
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Milk),
+            Ok(60)
+        );
+    }
+
+    #[test]
+    fn test10_many_ingredients_are_replenished_from_the_same_resource_with_their_rules() {
+        let mut config = CoffeeMakerConfig::new(0, 0, 50);
+        config.containers.insert("milk".to_owned(), 100);
+        config.containers.insert("steamed_milk".to_owned(), 0);
+        config.capacities.insert("steamed_milk".to_owned(), 100);
+        config.replenish_rules.push(ReplenishRuleConfig {
+            target: "steamed_milk".to_owned(),
+            source: "milk".to_owned(),
+            amount: Some(20),
+            ratio: Some(1.5),
+        });
+        let steamed_milk = Ingredient::Other("steamed_milk".to_owned());
+        let mut handler =
+            IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
+
+        assert_eq!(handler.get_index(&steamed_milk), Ok(3));
+        handler
+            .replenish(&Ingredient::Foam)
+            .expect("Error when replenishing foam");
+        handler
+            .replenish(&steamed_milk)
+            .expect("Error when replenishing steamed milk");
 
-        assert_eq!(coffee_got, false);
-        assert_eq!(foam_got, false);
-        assert_eq!(water_got, false);
+        assert_eq!(handler.containers.get_quantity_of(&steamed_milk), Ok(30));
+        assert_eq!(
+            handler.containers.get_quantity_of(&Ingredient::Milk),
+            Ok(30)
+        );
+        assert_eq!(handler.get_replenished()[&steamed_milk], 30);
+        assert_eq!(handler.get_replenished()[&Ingredient::Foam], 50);
+    }
+
+    #[test]
+    fn test11_every_container_at_an_alert_level_has_a_pending_alert() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("foam".to_owned(), 10);
+        config.containers.insert("sugar".to_owned(), 0);
+        config.capacities.insert("sugar".to_owned(), 40);
+        config.capacities.insert("milk".to_owned(), 400);
+        let handler = IHandler::new(Containers::with_clock(&config, Clock::simulated()), &config);
+
+        assert_eq!(
+            handler.get_pending_alerts(),
+            Ok(vec![
+                Ingredient::Foam,
+                Ingredient::Milk,
+                Ingredient::Other("sugar".to_owned())
+            ])
+        );
     }
 }
@@ -0,0 +1,400 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::Error,
+    units::{Quantity, Unit},
+};
+
+/// Name of the hot water in the orders.
+pub const WATER: &str = "water";
+
+/// Ingredients that every coffee maker has a container of.
+pub const BASE_INGREDIENTS: [Ingredient; 7] = [
+    Ingredient::Coffee,
+    Ingredient::HotWater,
+    Ingredient::Cocoa,
+    Ingredient::Foam,
+    Ingredient::GrainCoffee,
+    Ingredient::Milk,
+    Ingredient::ColdWater,
+];
+
+/// Ingredients that are replenished from a resource by default, in the order of their
+/// replenish rules.
+pub const REPLENISHABLE_INGREDIENTS: [Ingredient; 3] =
+    [Ingredient::Coffee, Ingredient::HotWater, Ingredient::Foam];
+
+/// Grams of ground coffee of a shot of espresso.
+const GRAMS_PER_SHOT: f64 = 7.0;
+/// Milliliters of a shot.
+const MILLILITERS_PER_SHOT: f64 = 30.0;
+
+/// Margin for the rounding errors when a quantity is multiplied by a yield ratio.
+const YIELD_TOLERANCE: f64 = 1e-9;
+
+/// Returns the quantity of an ingredient obtained from the quantity of its resource
+/// received with the yield ratio received, rounded down.
+pub fn get_yield(resource_quantity: u32, ratio: f64) -> u32 {
+    (resource_quantity as f64 * ratio + YIELD_TOLERANCE).floor() as u32
+}
+
+/// A rule to replenish an ingredient from a resource: how much of the resource is taken
+/// every time it is replenished and how much of the ingredient each unit of it yields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplenishRule {
+    pub target: Ingredient,
+    pub source: Ingredient,
+    pub amount: u32,
+    pub ratio: f64,
+}
+
+/// An ingredient that has a container in a coffee maker: one of the base ingredients or
+/// one added in the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Ingredient {
+    Coffee,
+    HotWater,
+    Cocoa,
+    Foam,
+    GrainCoffee,
+    Milk,
+    ColdWater,
+    Other(String),
+}
+
+impl Ingredient {
+    /// Returns the ingredient with the name received. The names that are not of a base
+    /// ingredient are of an ingredient added in the configuration.
+    pub fn from_name(name: &str) -> Ingredient {
+        match BASE_INGREDIENTS
+            .iter()
+            .find(|ingredient| ingredient.name() == name)
+        {
+            Some(ingredient) => ingredient.clone(),
+            None => Ingredient::Other(name.to_owned()),
+        }
+    }
+
+    /// Returns the ingredient whose container an ingredient of an order is taken from.
+    /// The water of an order is hot water.
+    pub fn from_order_name(name: &str) -> Ingredient {
+        match name {
+            WATER => Ingredient::HotWater,
+            _ => Ingredient::from_name(name),
+        }
+    }
+
+    /// Returns the name of the ingredient.
+    pub fn name(&self) -> &str {
+        match self {
+            Ingredient::Coffee => "coffee",
+            Ingredient::HotWater => "hot_water",
+            Ingredient::Cocoa => "cocoa",
+            Ingredient::Foam => "foam",
+            Ingredient::GrainCoffee => "grain_coffee",
+            Ingredient::Milk => "milk",
+            Ingredient::ColdWater => "cold_water",
+            Ingredient::Other(name) => name,
+        }
+    }
+
+    /// Returns the resource the ingredient is replenished from by default, if it has one.
+    pub fn get_resource(&self) -> Option<Ingredient> {
+        match self {
+            Ingredient::Coffee => Some(Ingredient::GrainCoffee),
+            Ingredient::HotWater => Some(Ingredient::ColdWater),
+            Ingredient::Foam => Some(Ingredient::Milk),
+            _ => None,
+        }
+    }
+
+    /// Returns the unit that the container of the ingredient is measured in. The
+    /// ingredients added in the configuration are measured in grams unless it sets
+    /// another unit.
+    pub fn get_unit(&self) -> Unit {
+        match self {
+            Ingredient::HotWater | Ingredient::Foam | Ingredient::Milk | Ingredient::ColdWater => {
+                Unit::Milliliters
+            }
+            _ => Unit::Grams,
+        }
+    }
+
+    /// Returns how much of the unit of its container is one of the unit received, if it
+    /// can be converted. A shot of coffee is 7 g of ground coffee or 30 ml of espresso, a
+    /// shot of a liquid is 30 ml and a gram of a liquid is a milliliter. The units of the
+    /// ingredients added in the configuration are not converted.
+    pub fn get_conversion(&self, unit: Unit) -> Option<f64> {
+        if let Ingredient::Other(_) = self {
+            return None;
+        }
+        if unit == self.get_unit() {
+            return Some(1.0);
+        }
+
+        match (self, unit) {
+            (Ingredient::Coffee | Ingredient::GrainCoffee, Unit::Shots) => Some(GRAMS_PER_SHOT),
+            (Ingredient::Coffee, Unit::Milliliters) => Some(GRAMS_PER_SHOT / MILLILITERS_PER_SHOT),
+            (Ingredient::Coffee | Ingredient::GrainCoffee | Ingredient::Cocoa, _) => None,
+            (_, Unit::Shots) => Some(MILLILITERS_PER_SHOT),
+            (_, Unit::Grams) => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the quantity received in the unit of the container of the ingredient,
+    /// rounded, returns an error if it can't be converted.
+    pub fn convert(&self, quantity: &Quantity) -> Result<u32, Error> {
+        self.apply_conversion(self.get_conversion(quantity.unit), quantity)
+    }
+
+    /// Returns the quantity received multiplied by the conversion received, rounded,
+    /// returns an error if there is no conversion or the result is not a quantity.
+    fn apply_conversion(&self, conversion: Option<f64>, quantity: &Quantity) -> Result<u32, Error> {
+        let Some(conversion) = conversion else {
+            return Err(Error::CantConvertUnit(
+                self.name().to_owned(),
+                quantity.unit,
+            ));
+        };
+        let value = (quantity.amount * conversion).round();
+        if !(0.0..=u32::MAX as f64).contains(&value) {
+            return Err(Error::WrongQuantity(quantity.to_string()));
+        }
+
+        Ok(value as u32)
+    }
+}
+
+impl fmt::Display for Ingredient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl From<String> for Ingredient {
+    fn from(name: String) -> Self {
+        Ingredient::from_name(&name)
+    }
+}
+
+impl From<Ingredient> for String {
+    fn from(ingredient: Ingredient) -> Self {
+        ingredient.name().to_owned()
+    }
+}
+
+/// Ingredients that a coffee maker knows, with the unit of their containers: the base
+/// ones and the ones added in its configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngredientRegistry {
+    ingredients: BTreeMap<Ingredient, Unit>,
+}
+
+impl IngredientRegistry {
+    /// Creates a new [`IngredientRegistry`] with the base ingredients.
+    pub fn new() -> IngredientRegistry {
+        IngredientRegistry {
+            ingredients: BASE_INGREDIENTS
+                .into_iter()
+                .map(|ingredient| {
+                    let unit = ingredient.get_unit();
+                    (ingredient, unit)
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds the ingredient with the name received and returns it.
+    pub fn register(&mut self, name: &str) -> Ingredient {
+        let ingredient = Ingredient::from_name(name);
+        self.ingredients
+            .entry(ingredient.clone())
+            .or_insert(ingredient.get_unit());
+        ingredient
+    }
+
+    /// Adds the ingredient with the name received measured in the unit received and
+    /// returns it. The unit of a base ingredient can't be changed.
+    pub fn register_with_unit(&mut self, name: &str, unit: Unit) -> Ingredient {
+        let ingredient = self.register(name);
+        if let Ingredient::Other(_) = ingredient {
+            self.ingredients.insert(ingredient.clone(), unit);
+        }
+        ingredient
+    }
+
+    /// Returns true if the ingredient is known.
+    pub fn contains(&self, ingredient: &Ingredient) -> bool {
+        self.ingredients.contains_key(ingredient)
+    }
+
+    /// Returns the unit that the container of the ingredient received is measured in.
+    pub fn get_unit(&self, ingredient: &Ingredient) -> Unit {
+        match self.ingredients.get(ingredient) {
+            Some(unit) => *unit,
+            None => ingredient.get_unit(),
+        }
+    }
+
+    /// Returns how much of the unit of the container of the ingredient received is one of
+    /// the unit received, if it can be converted. The ingredients added in the
+    /// configuration can only be converted from the unit of their container.
+    pub fn get_conversion(&self, ingredient: &Ingredient, unit: Unit) -> Option<f64> {
+        match ingredient {
+            Ingredient::Other(_) if unit == self.get_unit(ingredient) => Some(1.0),
+            _ => ingredient.get_conversion(unit),
+        }
+    }
+
+    /// Returns the quantity received in the unit of the container of the ingredient
+    /// received, rounded, returns an error if it can't be converted.
+    pub fn convert(&self, ingredient: &Ingredient, quantity: &Quantity) -> Result<u32, Error> {
+        ingredient.apply_conversion(self.get_conversion(ingredient, quantity.unit), quantity)
+    }
+
+    /// Returns the quantity received with the unit of the container of the ingredient.
+    pub fn get_quantity(&self, ingredient: &Ingredient, value: u32) -> Quantity {
+        Quantity::new(value as f64, self.get_unit(ingredient))
+    }
+
+    /// Returns the known ingredient with the name received, or an error if it is unknown.
+    pub fn get(&self, name: &str) -> Result<Ingredient, Error> {
+        let ingredient = Ingredient::from_name(name);
+        if !self.contains(&ingredient) {
+            return Err(Error::UnknownIngredient(name.to_owned()));
+        }
+
+        Ok(ingredient)
+    }
+
+    /// Returns the ingredient whose container an ingredient of an order is taken from,
+    /// or an error if it is unknown.
+    pub fn get_container_of(&self, name: &str) -> Result<Ingredient, Error> {
+        let ingredient = Ingredient::from_order_name(name);
+        if !self.contains(&ingredient) {
+            return Err(Error::UnknownIngredient(name.to_owned()));
+        }
+
+        Ok(ingredient)
+    }
+
+    /// Returns all the known ingredients, sorted.
+    pub fn get_all(&self) -> Vec<Ingredient> {
+        self.ingredients.keys().cloned().collect()
+    }
+}
+
+impl Default for IngredientRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::Error,
+        ingredients::{Ingredient, IngredientRegistry},
+        units::{Quantity, Unit},
+    };
+
+    #[test]
+    fn test01_get_an_ingredient_from_its_name() {
+        assert_eq!(Ingredient::from_name("hot_water"), Ingredient::HotWater);
+        assert_eq!(Ingredient::from_name("grain_coffee").name(), "grain_coffee");
+        assert_eq!(
+            Ingredient::from_name("sugar"),
+            Ingredient::Other("sugar".to_string())
+        );
+        assert_eq!(Ingredient::Foam.get_resource(), Some(Ingredient::Milk));
+        assert_eq!(Ingredient::Cocoa.get_resource(), None);
+    }
+
+    #[test]
+    fn test02_an_ingredient_is_converted_to_json_as_its_name() {
+        let json = serde_json::to_string(&vec![Ingredient::ColdWater]).expect("Valid json");
+        let ingredients: Vec<Ingredient> =
+            serde_json::from_str("[\"milk\", \"tea\"]").expect("Valid json");
+
+        assert_eq!(json, "[\"cold_water\"]");
+        assert_eq!(
+            ingredients,
+            vec![Ingredient::Milk, Ingredient::Other("tea".to_string())]
+        );
+    }
+
+    #[test]
+    fn test03_the_registry_knows_the_base_and_the_registered_ingredients() {
+        let mut registry = IngredientRegistry::new();
+        registry.register("sugar");
+
+        assert_eq!(registry.get("coffee"), Ok(Ingredient::Coffee));
+        assert_eq!(
+            registry.get("sugar"),
+            Ok(Ingredient::Other("sugar".to_string()))
+        );
+        assert_eq!(registry.get_container_of("water"), Ok(Ingredient::HotWater));
+        assert_eq!(
+            registry.get("cofee"),
+            Err(Error::UnknownIngredient("cofee".to_string()))
+        );
+        assert_eq!(registry.get_all().len(), 8);
+    }
+
+    #[test]
+    fn test04_convert_a_quantity_to_the_unit_of_the_container() {
+        let shot = Quantity::new(1.0, Unit::Shots);
+
+        assert_eq!(Ingredient::Coffee.convert(&shot), Ok(7));
+        assert_eq!(
+            Ingredient::Coffee.convert(&Quantity::new(30.0, Unit::Milliliters)),
+            Ok(7)
+        );
+        assert_eq!(Ingredient::HotWater.convert(&shot), Ok(30));
+        assert_eq!(
+            Ingredient::Milk.convert(&Quantity::new(20.0, Unit::Grams)),
+            Ok(20)
+        );
+        assert_eq!(
+            Ingredient::Cocoa.convert(&shot),
+            Err(Error::CantConvertUnit("cocoa".to_string(), Unit::Shots))
+        );
+    }
+
+    #[test]
+    fn test05_the_registry_knows_the_unit_of_every_container() {
+        let mut registry = IngredientRegistry::new();
+        let tea = registry.register_with_unit("tea", Unit::Milliliters);
+        registry.register_with_unit("coffee", Unit::Shots);
+
+        assert_eq!(registry.get_unit(&tea), Unit::Milliliters);
+        assert_eq!(registry.get_unit(&Ingredient::Coffee), Unit::Grams);
+        assert_eq!(
+            registry.get_quantity(&Ingredient::Foam, 10).to_string(),
+            "10 ml"
+        );
+    }
+
+    #[test]
+    fn test06_an_ingredient_of_the_configuration_is_converted_only_from_its_unit() {
+        let mut registry = IngredientRegistry::new();
+        let tea = registry.register_with_unit("tea", Unit::Milliliters);
+
+        assert_eq!(
+            registry.convert(&tea, &Quantity::new(10.0, Unit::Milliliters)),
+            Ok(10)
+        );
+        assert_eq!(
+            registry.convert(&tea, &Quantity::new(1.0, Unit::Shots)),
+            Err(Error::CantConvertUnit("tea".to_string(), Unit::Shots))
+        );
+        assert_eq!(
+            registry.convert(&Ingredient::Coffee, &Quantity::new(1.0, Unit::Shots)),
+            Ok(7)
+        );
+    }
+}
@@ -1,71 +1,612 @@
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{errors::Error, orders::Order};
+use serde_json::{value::RawValue, Value};
+
+use crate::{
+    errors::Error,
+    orders::{Order, OrderQuantity, OrderRequest},
+    orders_queue::OrdersQueue,
+    recipes::RecipeCatalog,
+    validation::{get_position, OrderValidator, ValidationError},
+};
+
+const RESOURCES_DIR: &str = "resources/";
+const RECIPES_FILENAME: &str = "recipes.json";
+const STREAM_EXTENSIONS: [&str; 2] = ["ndjson", "jsonl"];
+const STDIN: &str = "-";
+const CSV_EXTENSION: &str = "csv";
+const CSV_SEPARATOR: char = ',';
+const CSV_ID: &str = "id";
+const CSV_PRIORITY: &str = "priority";
+const CSV_ARRIVAL_MS: &str = "arrival_ms";
+const CSV_COLUMNS: [&str; 3] = [CSV_ID, CSV_PRIORITY, CSV_ARRIVAL_MS];
+const ID: &str = "id";
+const RECIPE: &str = "recipe";
+
+/// A destination of the orders read from the inputs.
+pub trait OrdersSink {
+    /// Adds the order received, returns an error if it can't.
+    fn push(&mut self, order: Order) -> Result<(), Error>;
+}
+
+impl OrdersSink for &OrdersQueue {
+    fn push(&mut self, order: Order) -> Result<(), Error> {
+        OrdersQueue::push(self, order)
+    }
+}
+
+impl OrdersSink for Vec<Order> {
+    fn push(&mut self, order: Order) -> Result<(), Error> {
+        Vec::push(self, order);
+        Ok(())
+    }
+}
+
+impl<S: OrdersSink + ?Sized> OrdersSink for &mut S {
+    fn push(&mut self, order: Order) -> Result<(), Error> {
+        (**self).push(order)
+    }
+}
+
+/// Format of the orders of an input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrdersFormat {
+    Json,
+    Csv,
+}
+
+impl OrdersFormat {
+    /// Returns the format with the name received if there is one,
+    /// returns an error if not.
+    pub fn from_name(name: &str) -> Result<OrdersFormat, Error> {
+        match name.to_lowercase().as_str() {
+            "json" => Ok(OrdersFormat::Json),
+            "csv" => Ok(OrdersFormat::Csv),
+            _ => Err(Error::UnknownFormat(name.to_owned())),
+        }
+    }
+}
+
+/// Position of every column of a csv file of orders.
+#[derive(Clone, Debug)]
+pub struct CsvColumns {
+    id: Option<usize>,
+    priority: Option<usize>,
+    arrival_ms: Option<usize>,
+    ingredients: Vec<(String, usize)>,
+}
+
+impl CsvColumns {
+    /// Gets the position of the columns from the header of a csv file of orders.
+    /// Every column but the id, the priority and the arrival time is an ingredient. Returns an error if a column has no name
+    /// or if there are no ingredients.
+    pub fn from_header(header: &str) -> Result<CsvColumns, Error> {
+        let names: Vec<String> = header
+            .split(CSV_SEPARATOR)
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+
+        let mut ingredients = Vec::new();
+        for (idx, name) in names.iter().enumerate() {
+            if name.is_empty() {
+                return Err(Error::WrongFileFormat);
+            }
+            if !CSV_COLUMNS.contains(&name.as_str()) {
+                ingredients.push((name.clone(), idx));
+            }
+        }
+        if ingredients.is_empty() {
+            return Err(Error::WrongFileFormat);
+        }
+
+        Ok(CsvColumns {
+            id: names.iter().position(|name| name == CSV_ID),
+            priority: names.iter().position(|name| name == CSV_PRIORITY),
+            arrival_ms: names.iter().position(|name| name == CSV_ARRIVAL_MS),
+            ingredients,
+        })
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct InputController {
-    pub filename: String,
+    pub filenames: Vec<String>,
+    pub recipes: RecipeCatalog,
+    pub format: Option<OrdersFormat>,
+    pub validator: OrderValidator,
 }
 
 impl InputController {
-    /// Creates an [`InputController`].
+    /// Creates an [`InputController`] that reads the orders of all the filenames received
+    /// in order. The filename `-` stands for the standard input.
     ///
     /// # Errors
     ///
     /// This function will return an error if the user does not enter a filename.
-    pub fn new(input: Option<String>) -> Result<InputController, Error> {
-        let file = match input {
-            Some(file) => file,
-            None => return Err(Error::NotFileInput),
-        };
+    pub fn new(inputs: Vec<String>) -> Result<InputController, Error> {
+        if inputs.is_empty() {
+            return Err(Error::NotFileInput);
+        }
 
-        Ok(InputController { filename: file })
+        Ok(InputController {
+            filenames: inputs,
+            recipes: RecipeCatalog::default(),
+            format: None,
+            validator: OrderValidator::default(),
+        })
     }
 
     /// Converts the orders from a json file to a vector of orders if it can,
-    /// returns an error if not.
+    /// returns an error with all the problems of the file if not.
+    /// The orders that name a recipe are resolved from its recipe catalog and the orders
+    /// without id get the next ids after the greatest id of the file.
     pub fn deserialize(self, orders: &str) -> Result<Vec<Order>, Error> {
-        let result = match serde_json::from_str::<Vec<Order>>(orders) {
-            Ok(orders) => orders,
-            Err(_) => return Err(Error::WrongFileFormat),
+        self.deserialize_from(orders, 0)
+    }
+
+    /// Converts the orders from a json file to a vector of orders if it can,
+    /// returns an error with all the problems of the file if not.
+    /// The orders without id get the next ids after the greatest id of the file, starting
+    /// at least from the first id received.
+    fn deserialize_from(&self, orders: &str, first_id: u32) -> Result<Vec<Order>, Error> {
+        let raw_orders = match serde_json::from_str::<Vec<&RawValue>>(orders) {
+            Ok(raw_orders) => raw_orders,
+            Err(err) => return Err(InputController::get_syntax_error(&err, err.line())),
+        };
+
+        let mut values = Vec::new();
+        for raw_order in &raw_orders {
+            match serde_json::from_str::<Value>(raw_order.get()) {
+                Ok(value) => values.push(value),
+                Err(err) => return Err(InputController::get_syntax_error(&err, err.line())),
+            }
+        }
+
+        let max_id = values
+            .iter()
+            .filter_map(|value| value.get(ID).and_then(|id| id.as_u64()))
+            .filter_map(|id| u32::try_from(id).ok())
+            .max();
+        let mut next_id = match max_id {
+            Some(max_id) => first_id.max(max_id.saturating_add(1)),
+            None => first_id,
         };
+        let mut result = Vec::new();
+        let mut errors = Vec::new();
+        for (idx, value) in values.into_iter().enumerate() {
+            let default_id = next_id;
+            if value.get(ID).is_none() {
+                next_id = next_id.saturating_add(1);
+            }
+
+            let offset = raw_orders[idx].get().as_ptr() as usize - orders.as_ptr() as usize;
+            let (line, column) = get_position(orders, offset);
+            match self.validate_order(value, default_id) {
+                Ok(order) => result.push(order),
+                Err(order_errors) => errors.extend(
+                    order_errors
+                        .into_iter()
+                        .map(|error| error.at(line, column, Some(idx))),
+                ),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::InvalidOrders(errors));
+        }
 
         Ok(result)
     }
 
-    /// Reads the filename entered from user and returns a vector of orders if it can,
+    /// Converts a json syntax error to an error of invalid orders at the line received.
+    fn get_syntax_error(err: &serde_json::Error, line: usize) -> Error {
+        Error::InvalidOrders(vec![ValidationError::new(None, err.to_string()).at(
+            line,
+            err.column().max(1),
+            None,
+        )])
+    }
+
+    /// Converts an order written in json to an order if it is valid,
+    /// returns all its problems if not.
+    fn validate_order(&self, order: Value, default_id: u32) -> Result<Order, Vec<ValidationError>> {
+        let errors = self.validator.check_fields(&order);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let order = self.validator.convert_quantities(order);
+        let request = match serde_json::from_value::<OrderRequest>(order) {
+            Ok(request) => request,
+            Err(err) => return Err(vec![ValidationError::new(None, err.to_string())]),
+        };
+        let order = match request.resolve(&self.recipes, default_id) {
+            Ok(order) => order,
+            Err(Error::UnknownRecipe(recipe)) => {
+                return Err(vec![ValidationError::new(
+                    Some(RECIPE),
+                    format!("unknown recipe {}", recipe),
+                )])
+            }
+            Err(err) => return Err(vec![ValidationError::new(None, format!("{:?}", err))]),
+        };
+
+        let errors = self.validator.check_order(&order);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(order)
+    }
+
+    /// Shows the problems of an order that is skipped.
+    fn report_skipped(errors: Vec<ValidationError>) {
+        for error in errors {
+            println!("[INPUT CONTROLLER]: {:?} SKIPPING ORDER", error);
+        }
+    }
+
+    /// Returns the path of the file received. Relative paths that do not exist from the
+    /// current directory are looked for in the resources directory.
+    pub(crate) fn get_path(filename: &str) -> PathBuf {
+        let path = Path::new(filename);
+        if path.is_absolute() || path.exists() {
+            return path.to_path_buf();
+        }
+
+        Path::new(RESOURCES_DIR).join(path)
+    }
+
+    /// Converts an error of reading a file to an error of the program.
+    fn get_read_error(err: io::Error) -> Error {
+        match err.kind() {
+            ErrorKind::NotFound => Error::FileNotFound,
+            _ => Error::CantReadFile,
+        }
+    }
+
+    /// Reads the recipes from the recipes file of the same directory of the orders file,
+    /// if there is one. The recipes of the standard input are the ones of the current
+    /// directory.
+    fn load_recipes(&mut self, filename: &str) -> Result<(), Error> {
+        let recipes_path = if filename == STDIN {
+            InputController::get_path(RECIPES_FILENAME)
+        } else {
+            match InputController::get_path(filename).parent() {
+                Some(orders_dir) => orders_dir.join(RECIPES_FILENAME),
+                None => return Ok(()),
+            }
+        };
+        self.recipes = RecipeCatalog::from_file(&recipes_path)?;
+
+        Ok(())
+    }
+
+    /// Opens the file received, or the standard input if it is `-`.
+    fn open(filename: &str) -> Result<Box<dyn BufRead>, Error> {
+        if filename == STDIN {
+            return Ok(Box::new(BufReader::new(io::stdin())));
+        }
+
+        match File::open(InputController::get_path(filename)) {
+            Ok(file) => Ok(Box::new(BufReader::new(file))),
+            Err(err) => Err(InputController::get_read_error(err)),
+        }
+    }
+
+    /// Returns true if the content of the reader is a json array of orders, false if it
+    /// has one json order per line. The leading whitespaces are skipped.
+    fn is_json_array<R: BufRead + ?Sized>(reader: &mut R) -> Result<bool, Error> {
+        loop {
+            let buffer = match reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(err) => return Err(InputController::get_read_error(err)),
+            };
+            let Some(&first) = buffer.first() else {
+                return Ok(false);
+            };
+            if !first.is_ascii_whitespace() {
+                return Ok(first == b'[');
+            }
+            reader.consume(1);
+        }
+    }
+
+    /// Returns the format of the file received: the format entered from user if there is
+    /// one, or the one of its extension if not.
+    fn get_format(&self, filename: &str) -> OrdersFormat {
+        if let Some(format) = self.format {
+            return format;
+        }
+
+        match Path::new(filename).extension() {
+            Some(extension) if extension == CSV_EXTENSION => OrdersFormat::Csv,
+            _ => OrdersFormat::Json,
+        }
+    }
+
+    /// Converts a row of a csv file of orders to an order if it can,
+    /// returns an error with the number of the row if not.
+    /// The empty ingredients and priority are zero, an empty arrival time means that the
+    /// order is available from the start, and the order gets the id of its row or the
+    /// default id received if it is empty. The ingredients can have a unit, like "1 shot".
+    pub fn deserialize_csv_row(
+        &self,
+        columns: &CsvColumns,
+        line: &str,
+        row: usize,
+        default_id: u32,
+    ) -> Result<Order, Error> {
+        let fields: Vec<&str> = line
+            .split(CSV_SEPARATOR)
+            .map(|field| field.trim())
+            .collect();
+        let parse = |idx: usize| match fields.get(idx).map(|field| field.parse::<u32>()) {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(_)) if fields[idx].is_empty() => Ok(0),
+            _ => Err(Error::WrongCsvRow(row)),
+        };
+        let parse_quantity = |ingredient: &str, idx: usize| match parse(idx) {
+            Ok(value) => Ok(value),
+            Err(err) => match fields.get(idx).map(|field| {
+                OrderQuantity::WithUnit(field.to_string())
+                    .get_value(ingredient, self.validator.get_registry())
+            }) {
+                Some(Ok(value)) => Ok(value),
+                _ => Err(err),
+            },
+        };
+
+        let mut ingredients = HashMap::new();
+        for (ingredient, idx) in &columns.ingredients {
+            ingredients.insert(ingredient.clone(), parse_quantity(ingredient, *idx)?);
+        }
+        let id = match columns.id {
+            Some(idx) if fields.get(idx).is_some_and(|field| !field.is_empty()) => parse(idx)?,
+            _ => default_id,
+        };
+        let priority = match columns.priority {
+            Some(idx) => parse(idx)?,
+            None => 0,
+        };
+
+        let mut order = Order::from(ingredients).with_id(id).with_priority(priority);
+        if let Some(idx) = columns.arrival_ms {
+            match fields.get(idx).map(|field| field.parse::<u64>()) {
+                Some(Ok(arrival_ms)) => order = order.with_arrival_ms(arrival_ms),
+                Some(Err(_)) if fields[idx].is_empty() => {}
+                _ => return Err(Error::WrongCsvRow(row)),
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Reads the orders from a csv reader one row at a time and pushes them to the sink.
+    /// The first row must be the header with the ingredients columns and optionally an id,
+    /// a priority and an arrival time column. The rows that can't be converted to an order
+    /// are reported with their row number and skipped.
+    /// Returns the next id after the orders read.
+    pub fn read_csv<R: BufRead, S: OrdersSink>(
+        &self,
+        reader: R,
+        mut orders: S,
+        next_id: u32,
+    ) -> Result<u32, Error> {
+        let mut next_id = next_id;
+        let mut order_idx = 0;
+        let mut columns = None;
+        for (idx, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Err(InputController::get_read_error(err)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let Some(csv_columns) = &columns else {
+                columns = Some(CsvColumns::from_header(&line)?);
+                continue;
+            };
+            match self.deserialize_csv_row(csv_columns, &line, idx + 1, next_id) {
+                Ok(order) => {
+                    let errors = self.validator.check_order(&order);
+                    if !errors.is_empty() {
+                        InputController::report_skipped(
+                            errors
+                                .into_iter()
+                                .map(|error| error.at(idx + 1, 1, Some(order_idx)))
+                                .collect(),
+                        );
+                    } else {
+                        next_id = next_id.max(order.id.saturating_add(1));
+                        orders.push(order)?;
+                    }
+                }
+                Err(err) => println!("[INPUT CONTROLLER]: {:?} SKIPPING ROW", err),
+            }
+            order_idx += 1;
+        }
+
+        Ok(next_id)
+    }
+
+    /// Reads the filenames entered from user and returns a vector of orders if it can,
+    /// returns an error if not. The orders are returned in the order they were read.
+    /// The recipes are read from the recipes file of the same directory, if there is one.
+    pub fn get_orders(mut self) -> Result<Vec<Order>, Error> {
+        let mut orders = Vec::new();
+        self.push_orders(&mut orders)?;
+
+        Ok(orders)
+    }
+
+    /// Returns true if any of the inputs has one order per line (json lines or csv) or is
+    /// the standard input, so its orders can be streamed instead of being read all at once.
+    pub fn is_stream(&self) -> bool {
+        self.filenames.iter().any(|filename| {
+            filename == STDIN
+                || self.get_format(filename) == OrdersFormat::Csv
+                || match Path::new(filename).extension() {
+                    Some(extension) => STREAM_EXTENSIONS.iter().any(|ext| extension == *ext),
+                    None => false,
+                }
+        })
+    }
+
+    /// Converts a line of a stream of orders to an order if it can,
     /// returns an error if not.
-    pub fn get_orders(self) -> Result<Vec<Order>, Error> {
-        let dir = Path::new("resources/");
-        let file = Path::new(&self.filename);
-        let path = dir.join(file);
-
-        let orders = match std::fs::read_to_string(path) {
-            Ok(orders) => orders,
-            Err(_e) => return Err(Error::FileNotFound),
+    /// The problems of the order are at the line 1.
+    pub fn deserialize_line(&self, line: &str, default_id: u32) -> Result<Order, Error> {
+        let value = match serde_json::from_str::<Value>(line) {
+            Ok(value) => value,
+            Err(err) => return Err(InputController::get_syntax_error(&err, 1)),
         };
 
-        Ok(self.deserialize(&orders))?
+        match self.validate_order(value, default_id) {
+            Ok(order) => Ok(order),
+            Err(errors) => Err(Error::InvalidOrders(
+                errors
+                    .into_iter()
+                    .map(|error| error.at(1, 1, None))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Reads the orders from the reader one line at a time and pushes them to the sink, so
+    /// with a queue the dispensers can take them while the rest are being read.
+    /// The invalid orders are reported with their line and skipped.
+    /// The orders without id get the next id after the greatest id read so far, starting
+    /// at least from the next id received. Returns the next id after the orders read.
+    pub fn read_stream<R: BufRead, S: OrdersSink>(
+        &self,
+        reader: R,
+        mut orders: S,
+        next_id: u32,
+    ) -> Result<u32, Error> {
+        let mut next_id = next_id;
+        let mut order_idx = 0;
+        for (idx, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Err(InputController::get_read_error(err)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match self.deserialize_line(&line, next_id) {
+                Ok(order) => {
+                    next_id = next_id.max(order.id.saturating_add(1));
+                    orders.push(order)?;
+                }
+                Err(Error::InvalidOrders(errors)) => InputController::report_skipped(
+                    errors
+                        .into_iter()
+                        .map(|error| {
+                            let column = error.column;
+                            error.at(idx + 1, column, Some(order_idx))
+                        })
+                        .collect(),
+                ),
+                Err(err) => return Err(err),
+            }
+            order_idx += 1;
+        }
+
+        Ok(next_id)
+    }
+
+    /// Streams the orders of all the filenames entered from user, in order, to the queue
+    /// of orders.
+    /// The queue is closed when there are no more orders to read, even if there was
+    /// an error.
+    pub fn stream_orders(mut self, orders: Arc<OrdersQueue>) -> Result<(), Error> {
+        let result = self.push_orders(&mut orders.as_ref());
+        orders.close()?;
+
+        result
+    }
+
+    /// Pushes the orders of all the filenames to the sink.
+    fn push_orders<S: OrdersSink>(&mut self, orders: &mut S) -> Result<(), Error> {
+        let mut next_id = 0;
+        for filename in self.filenames.clone() {
+            next_id = self.push_orders_of(&filename, &mut *orders, next_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens the file received and pushes its orders to the sink, whether it has a json
+    /// array of orders, one json order per line or csv rows.
+    /// Returns the next id after the orders read.
+    fn push_orders_of<S: OrdersSink>(
+        &mut self,
+        filename: &str,
+        mut orders: S,
+        next_id: u32,
+    ) -> Result<u32, Error> {
+        self.load_recipes(filename)?;
+        let mut reader = InputController::open(filename)?;
+        if self.get_format(filename) == OrdersFormat::Csv {
+            return self.read_csv(reader, orders, next_id);
+        }
+        if !InputController::is_json_array(&mut reader)? {
+            return self.read_stream(reader, orders, next_id);
+        }
+
+        let mut content = String::new();
+        if let Err(err) = reader.read_to_string(&mut content) {
+            return Err(InputController::get_read_error(err));
+        }
+
+        let mut next_id = next_id;
+        for order in self.deserialize_from(&content, next_id)? {
+            next_id = next_id.max(order.id.saturating_add(1));
+            orders.push(order)?;
+        }
+
+        Ok(next_id)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{errors::Error, input_controller::InputController};
+    use std::{collections::HashMap, io::Cursor, sync::Arc};
+
+    use crate::{
+        config::Config,
+        errors::Error,
+        ingredients::Ingredient,
+        input_controller::{CsvColumns, InputController, OrdersFormat},
+        orders::Order,
+        orders_queue::OrdersQueue,
+        recipes::RecipeCatalog,
+        validation::{OrderValidator, ValidationError},
+    };
 
     #[test]
     fn test01_get_a_valid_filename() {
         let icontrolller =
-            InputController::new(Some("orders.json".to_string())).expect("The filename is invalid");
-        let expected_file = "orders.json".to_string();
-        let got_file = icontrolller.filename;
-        assert_eq!(expected_file, got_file);
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let expected_files = vec!["orders.json".to_string()];
+        let got_files = icontrolller.filenames;
+        assert_eq!(expected_files, got_files);
     }
 
     #[test]
     fn test02_not_get_a_filename() {
-        let result =
-            InputController::new(None).expect_err("You must enter a filename of the orders file");
+        let result = InputController::new(Vec::new())
+            .expect_err("You must enter a filename of the orders file");
         let err_expected = Error::NotFileInput;
 
         assert_eq!(result, err_expected);
@@ -73,7 +614,7 @@ mod tests {
 
     #[test]
     fn test03_get_a_not_found_filename() {
-        let icontroller = InputController::new(Some("pedidos.json".to_string()))
+        let icontroller = InputController::new(vec!["pedidos.json".to_string()])
             .expect("The filename is invalid");
         let result = icontroller
             .get_orders()
@@ -86,13 +627,448 @@ mod tests {
     #[test]
     fn test04_get_an_order_without_all_fields() {
         let icontroller =
-            InputController::new(Some("orders.json".to_string())).expect("The filename is invalid");
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
         let orders = "{\r\n    \"all\":[\r\n        {\r\n            \"water\": 10,\r\n            \"cocoa\": 2,\r\n            \"foam\": 2\r\n        }\r\n    ]\r\n}".to_string();
         let result = icontroller
             .deserialize(&orders)
             .expect_err("The order doesnt have all the ingredients");
-        let err_expected = Error::WrongFileFormat;
+
+        let Error::InvalidOrders(errors) = result else {
+            panic!("The error is not a validation error");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (1, 1));
+        assert_eq!(errors[0].order, None);
+    }
+
+    #[test]
+    fn test05_get_orders_with_recipes_of_the_catalog() {
+        let mut icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let mut recipes = HashMap::new();
+        recipes.insert("latte".to_string(), Order::new(10, 5, 0, 15));
+        icontroller.recipes = RecipeCatalog::new(recipes);
+        let orders =
+            "[{\"recipe\": \"latte\"}, {\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}]";
+        let result = icontroller
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].get("coffee"), 10);
+        assert_eq!(result[0].get("foam"), 15);
+        assert_eq!(result[1].get("coffee"), 1);
+        assert_eq!(result[1].get("foam"), 4);
+    }
+
+    #[test]
+    fn test06_get_an_order_with_an_unknown_recipe() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"recipe\": \"frappuccino\"}]";
+        let result = icontroller
+            .deserialize(orders)
+            .expect_err("The recipe is unknown");
+        let err_expected = Error::InvalidOrders(vec![ValidationError {
+            line: 1,
+            column: 2,
+            order: Some(0),
+            field: Some("recipe".to_string()),
+            message: "unknown recipe frappuccino".to_string(),
+        }]);
 
         assert_eq!(result, err_expected);
     }
+
+    #[test]
+    fn test07_get_orders_of_a_file_with_recipes() {
+        let icontroller = InputController::new(vec!["orders05.json".to_string()])
+            .expect("The filename is invalid");
+        let result = icontroller.get_orders().expect("The orders are invalid");
+
+        assert_eq!(result.len(), 10);
+    }
+
+    #[test]
+    fn test08_get_orders_with_and_without_ids() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}, {\"id\": 7, \"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}, {\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}]";
+        let result = icontroller
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].id, 8);
+        assert_eq!(result[1].id, 7);
+        assert_eq!(result[2].id, 9);
+    }
+
+    #[test]
+    fn test09_read_a_stream_of_orders_one_per_line() {
+        let icontroller = InputController::new(vec!["orders.ndjson".to_string()])
+            .expect("The filename is invalid");
+        let orders = "{\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n\n{\"id\": 5, \"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n{\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n";
+        let queue = OrdersQueue::new();
+        icontroller
+            .read_stream(Cursor::new(orders), &queue, 0)
+            .expect("The orders are invalid");
+
+        assert!(icontroller.is_stream());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.pop().expect("There are orders").id, 0);
+        assert_eq!(queue.pop().expect("There are orders").id, 5);
+        assert_eq!(queue.pop().expect("There are orders").id, 6);
+    }
+
+    #[test]
+    fn test10_stream_orders_of_a_file_and_close_the_queue() {
+        let icontroller = InputController::new(vec!["orders06.ndjson".to_string()])
+            .expect("The filename is invalid");
+        let queue = Arc::new(OrdersQueue::new());
+        icontroller
+            .stream_orders(queue.clone())
+            .expect("The orders are invalid");
+
+        assert_eq!(queue.len(), 10);
+        assert!(!queue.is_finished());
+        for _ in 0..10 {
+            queue.pop().expect("There are orders");
+        }
+        assert!(queue.is_finished());
+    }
+
+    #[test]
+    fn test11_stream_orders_of_a_not_found_file_closes_the_queue() {
+        let icontroller = InputController::new(vec!["pedidos.ndjson".to_string()])
+            .expect("The filename is invalid");
+        let queue = Arc::new(OrdersQueue::new());
+        let result = icontroller
+            .stream_orders(queue.clone())
+            .expect_err("The filename wasnt found");
+
+        assert_eq!(result, Error::FileNotFound);
+        assert!(queue.is_finished());
+    }
+
+    #[test]
+    fn test12_get_orders_of_several_files_in_order_with_relative_and_absolute_paths() {
+        let absolute_path = std::env::current_dir()
+            .expect("There is a current directory")
+            .join("resources/orders01.json");
+        let icontroller = InputController::new(vec![
+            "resources/orders05.json".to_string(),
+            absolute_path.display().to_string(),
+            "orders06.ndjson".to_string(),
+        ])
+        .expect("The filenames are invalid");
+        let result = icontroller.get_orders().expect("The orders are invalid");
+
+        assert_eq!(result.len(), 28);
+        for (idx, order) in result.iter().enumerate() {
+            assert_eq!(order.id, idx as u32);
+        }
+    }
+
+    #[test]
+    fn test13_get_orders_of_a_directory_is_a_read_error() {
+        let icontroller =
+            InputController::new(vec!["resources".to_string()]).expect("The filename is invalid");
+        let result = icontroller
+            .get_orders()
+            .expect_err("A directory cant be read");
+
+        assert_eq!(result, Error::CantReadFile);
+    }
+
+    #[test]
+    fn test14_read_csv_orders_with_and_without_ids() {
+        let icontroller =
+            InputController::new(vec!["orders.csv".to_string()]).expect("The filename is invalid");
+        let orders = "foam,coffee,water,cocoa,id\n4,1,2,3,\n4,1,2,3,7\n\n4,1,2,3\n";
+        let queue = OrdersQueue::new();
+        let next_id = icontroller
+            .read_csv(Cursor::new(orders), &queue, 0)
+            .expect("The orders are invalid");
+
+        assert_eq!(next_id, 9);
+        let order = queue.pop().expect("There are orders");
+        assert_eq!(order.id, 0);
+        assert_eq!(order.get("coffee"), 1);
+        assert_eq!(order.get("water"), 2);
+        assert_eq!(order.get("cocoa"), 3);
+        assert_eq!(order.get("foam"), 4);
+        assert_eq!(queue.pop().expect("There are orders").id, 7);
+        assert_eq!(queue.pop().expect("There are orders").id, 8);
+    }
+
+    #[test]
+    fn test15_read_csv_orders_skips_the_wrong_rows() {
+        let icontroller =
+            InputController::new(vec!["orders.csv".to_string()]).expect("The filename is invalid");
+        let orders = "coffee,water,cocoa,foam\n1,2,3,4\n1,two,3,4\n1,2,3\n1,2,3,4\n";
+        let queue = OrdersQueue::new();
+        icontroller
+            .read_csv(Cursor::new(orders), &queue, 0)
+            .expect("The orders are invalid");
+
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test16_get_the_row_number_of_a_wrong_csv_row() {
+        let icontroller =
+            InputController::new(vec!["orders.csv".to_string()]).expect("The filename is invalid");
+        let columns = CsvColumns::from_header("coffee,water,cocoa,foam").expect("Valid header");
+        let result = icontroller
+            .deserialize_csv_row(&columns, "1,2,-3,4", 5, 0)
+            .expect_err("The row has a negative quantity");
+
+        assert_eq!(result, Error::WrongCsvRow(5));
+    }
+
+    #[test]
+    fn test17_read_csv_orders_with_any_ingredients_columns() {
+        let icontroller =
+            InputController::new(vec!["orders.csv".to_string()]).expect("The filename is invalid");
+        let columns = CsvColumns::from_header("coffee,sugar").expect("Valid header");
+        let order = icontroller
+            .deserialize_csv_row(&columns, "10,", 1, 0)
+            .expect("The row is valid");
+
+        assert_eq!(order.get("coffee"), 10);
+        assert_eq!(order.get("sugar"), 0);
+        assert_eq!(order.get("water"), 0);
+
+        let result = CsvColumns::from_header("id").expect_err("The header has no ingredients");
+        assert_eq!(result, Error::WrongFileFormat);
+    }
+
+    #[test]
+    fn test18_get_orders_of_a_csv_file() {
+        let icontroller = InputController::new(vec!["orders07.csv".to_string()])
+            .expect("The filename is invalid");
+        let result = icontroller.get_orders().expect("The orders are invalid");
+
+        assert_eq!(result.len(), 10);
+    }
+
+    #[test]
+    fn test19_the_format_entered_from_user_is_used_for_every_file() {
+        let mut icontroller =
+            InputController::new(vec!["-".to_string()]).expect("The filename is invalid");
+        icontroller.format = Some(OrdersFormat::from_name("CSV").expect("The format is valid"));
+
+        assert_eq!(icontroller.get_format("-"), OrdersFormat::Csv);
+        assert_eq!(
+            OrdersFormat::from_name("xml").expect_err("The format is unknown"),
+            Error::UnknownFormat("xml".to_string())
+        );
+    }
+
+    #[test]
+    fn test20_get_the_position_of_a_syntax_error() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[\n  {\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4},\n  {\"coffee\": 1 \"water\": 2}\n]";
+        let result = icontroller
+            .deserialize(orders)
+            .expect_err("The file has a typo");
+
+        let Error::InvalidOrders(errors) = result else {
+            panic!("The error is not a validation error");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (3, 16));
+    }
+
+    #[test]
+    fn test21_get_all_the_problems_of_the_orders_at_once() {
+        let mut icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let mut max_quantities = HashMap::new();
+        for container in [Ingredient::HotWater, Ingredient::Cocoa, Ingredient::Foam] {
+            max_quantities.insert(container, 1000);
+        }
+        max_quantities.insert(Ingredient::Coffee, 100);
+        icontroller.validator = OrderValidator::new(max_quantities);
+        let orders = "[\n  {\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4},\n  {\"coffee\": 1, \"cocoa\": 3, \"foam\": -4},\n  {\"coffee\": 0, \"water\": 0, \"cocoa\": 0, \"foam\": 0},\n  {\"coffee\": 500, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n]";
+        let result = icontroller
+            .deserialize(orders)
+            .expect_err("The file has invalid orders");
+
+        let Error::InvalidOrders(errors) = result else {
+            panic!("The error is not a validation error");
+        };
+        let problems: Vec<(usize, Option<usize>, Option<String>)> = errors
+            .into_iter()
+            .map(|error| (error.line, error.order, error.field))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (3, Some(1), Some("foam".to_string())),
+                (4, Some(2), None),
+                (5, Some(3), Some("coffee".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test22_read_a_stream_of_orders_skips_the_invalid_lines() {
+        let icontroller = InputController::new(vec!["orders.ndjson".to_string()])
+            .expect("The filename is invalid");
+        let orders = "{\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n{\"coffee\": 1,\n{\"coffee\": 0, \"water\": 0, \"cocoa\": 0, \"foam\": 0}\n{\"coffee\": 1, \"water\": 2, \"cocoa\": 3, \"foam\": 4}\n";
+        let queue = OrdersQueue::new();
+        icontroller
+            .read_stream(Cursor::new(orders), &queue, 0)
+            .expect("The orders are valid");
+
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test23_get_orders_with_priority() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"priority\": 2, \"coffee\": 1}, {\"coffee\": 1}]";
+        let result = icontroller
+            .clone()
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].priority, 2);
+        assert_eq!(result[0].get("priority"), 0);
+        assert_eq!(result[1].priority, 0);
+
+        let columns = CsvColumns::from_header("coffee,priority").expect("Valid header");
+        let order = icontroller
+            .deserialize_csv_row(&columns, "10,3", 1, 0)
+            .expect("The row is valid");
+        assert_eq!(order.priority, 3);
+        assert_eq!(order.ingredients.len(), 1);
+    }
+
+    #[test]
+    fn test24_get_orders_with_arrival_time() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"arrival_ms\": 1500, \"coffee\": 1}, {\"coffee\": 1}]";
+        let result = icontroller
+            .clone()
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].arrival_ms, Some(1500));
+        assert_eq!(result[1].arrival_ms, None);
+        assert_eq!(result[0].ingredients.len(), 1);
+
+        let columns = CsvColumns::from_header("arrival_ms,coffee").expect("Valid header");
+        let order = icontroller
+            .deserialize_csv_row(&columns, "250,10", 1, 0)
+            .expect("The row is valid");
+        assert_eq!(order.arrival_ms, Some(250));
+        let result = icontroller
+            .deserialize_csv_row(&columns, "soon,10", 2, 0)
+            .expect_err("The arrival time is not a number");
+        assert_eq!(result, Error::WrongCsvRow(2));
+    }
+
+    #[test]
+    fn test25_get_orders_with_quantities_in_other_units() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"coffee\": \"1 shot\", \"water\": \"1 shot\"}, {\"coffee\": \"30 ml\"}]";
+        let result = icontroller
+            .clone()
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].get("coffee"), 7);
+        assert_eq!(result[0].get("water"), 30);
+        assert_eq!(result[1].get("coffee"), 7);
+
+        let columns = CsvColumns::from_header("coffee,foam").expect("Valid header");
+        let order = icontroller
+            .deserialize_csv_row(&columns, "2 shots,20 g", 1, 0)
+            .expect("The row is valid");
+        assert_eq!(order.get("coffee"), 14);
+        assert_eq!(order.get("foam"), 20);
+        let result = icontroller
+            .deserialize_csv_row(&columns, "2 cups,20", 2, 0)
+            .expect_err("The unit is unknown");
+        assert_eq!(result, Error::WrongCsvRow(2));
+    }
+
+    #[test]
+    fn test26_get_orders_with_the_greatest_id_and_ids_that_dont_fit() {
+        let icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let orders = "[{\"id\": 4294967295, \"coffee\": 1}, {\"coffee\": 1}]";
+        let result = icontroller
+            .clone()
+            .deserialize(orders)
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].id, u32::MAX);
+        assert_eq!(result.len(), 2);
+
+        let orders = "[{\"id\": 4294967296, \"coffee\": 1}, {\"coffee\": 1}]";
+        let Error::InvalidOrders(errors) = icontroller
+            .deserialize(orders)
+            .expect_err("The id doesnt fit")
+        else {
+            panic!("The orders should be invalid");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].order, Some(0));
+        assert_eq!(errors[0].field, Some("id".to_string()));
+    }
+
+    #[test]
+    fn test27_read_streams_of_orders_with_the_greatest_id() {
+        let icontroller = InputController::new(vec!["orders.ndjson".to_string()])
+            .expect("The filename is invalid");
+        let orders = "{\"id\": 4294967295, \"coffee\": 1}\n{\"id\": 4294967296, \"coffee\": 1}\n";
+        let queue = OrdersQueue::new();
+        let next_id = icontroller
+            .read_stream(Cursor::new(orders), &queue, 0)
+            .expect("The orders are valid");
+
+        assert_eq!(next_id, u32::MAX);
+        assert_eq!(queue.len(), 1);
+
+        let orders = "id,coffee\n4294967295,1\n4294967296,1\n";
+        let queue = OrdersQueue::new();
+        let next_id = icontroller
+            .read_csv(Cursor::new(orders), &queue, 0)
+            .expect("The orders are valid");
+
+        assert_eq!(next_id, u32::MAX);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test28_get_orders_of_an_ingredient_of_the_configuration_in_the_unit_of_its_container() {
+        let mut icontroller =
+            InputController::new(vec!["orders.json".to_string()]).expect("The filename is invalid");
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"tea\": 100}, \"units\": {\"tea\": \"ml\"}}]}";
+        icontroller.validator =
+            OrderValidator::from_config(&Config::deserialize(config).expect("Valid config"));
+        let result = icontroller
+            .clone()
+            .deserialize("[{\"tea\": \"10 ml\"}]")
+            .expect("The orders are invalid");
+
+        assert_eq!(result[0].get("tea"), 10);
+
+        let columns = CsvColumns::from_header("tea").expect("Valid header");
+        let order = icontroller
+            .deserialize_csv_row(&columns, "20 ml", 1, 0)
+            .expect("The row is valid");
+        assert_eq!(order.get("tea"), 20);
+        let result = icontroller
+            .deserialize_csv_row(&columns, "1 shot", 2, 0)
+            .expect_err("The tea is not measured in shots");
+        assert_eq!(result, Error::WrongCsvRow(2));
+    }
 }
@@ -1,11 +1,26 @@
+pub mod alert_sinks;
+pub mod alerts;
+pub mod atomic_container;
+pub mod audit;
+pub mod clock;
 pub mod coffee_maker;
+pub mod config;
 pub mod container;
 pub mod containers;
+pub mod control;
 pub mod dispensers;
 pub mod errors;
+pub mod feeder;
 pub mod ingredient_handler;
+pub mod ingredients;
 pub mod input_controller;
 pub mod orders;
 pub mod orders_handler;
+pub mod orders_queue;
+pub mod recipes;
+pub mod report;
+pub mod snapshot;
 pub mod stat_maker;
 pub mod stats_presenter;
+pub mod units;
+pub mod validation;
@@ -1,49 +1,231 @@
-use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread::{self, JoinHandle};
 
+use tp1::audit::AuditLog;
+use tp1::clock::Clock;
 use tp1::coffee_maker::CoffeeMaker;
+use tp1::config::Config;
+use tp1::control::{handle_commands, read_commands};
 use tp1::errors::Error;
-use tp1::input_controller::InputController;
+use tp1::feeder::{feed_orders, has_arrivals, set_poisson_arrivals, ArrivalGenerator};
+use tp1::input_controller::{InputController, OrdersFormat};
+use tp1::orders_queue::OrdersQueue;
+use tp1::report::Report;
+use tp1::snapshot::Snapshot;
 use tp1::stats_presenter::presenter::show_statistics;
+use tp1::validation::OrderValidator;
 
-const COFFEE_MAKERS: u32 = 2;
-const VALUE_TO_REPLENISH: u32 = 50;
-const INITIAL_QUANTITY: u32 = 100;
+const CONFIG_FLAG: &str = "--config";
+const REPORT_FLAG: &str = "--report";
+const FORMAT_FLAG: &str = "--format";
+const ARRIVAL_RATE_FLAG: &str = "--arrival-rate";
+const CLOCK_FLAG: &str = "--clock";
+const SPEED_UP_FLAG: &str = "--speed-up";
+const LOAD_STATE_FLAG: &str = "--load-state";
+const SAVE_STATE_FLAG: &str = "--save-state";
+const AUDIT_LOG_FLAG: &str = "--audit-log";
+const CONTROL_FLAG: &str = "--control";
+const FLAGS: [&str; 10] = [
+    CONFIG_FLAG,
+    REPORT_FLAG,
+    FORMAT_FLAG,
+    ARRIVAL_RATE_FLAG,
+    CLOCK_FLAG,
+    SPEED_UP_FLAG,
+    LOAD_STATE_FLAG,
+    SAVE_STATE_FLAG,
+    AUDIT_LOG_FLAG,
+    CONTROL_FLAG,
+];
+const REAL_CLOCK: &str = "real";
+const VIRTUAL_CLOCK: &str = "virtual";
+const STREAM_CAPACITY: usize = 100;
 
-/// Returns a list of CoffeeMaker.
-pub fn get_coffee_makers() -> Vec<CoffeeMaker> {
+type Reader = JoinHandle<Result<(), Error>>;
+
+/// Returns a list of CoffeeMaker built from the configuration that take their time from
+/// the clock received.
+pub fn get_coffee_makers(config: &Config, clock: &Clock) -> Vec<CoffeeMaker> {
     let mut coffee_makers = Vec::new();
-    for j in 0..COFFEE_MAKERS {
-        coffee_makers.push(CoffeeMaker::new(j, INITIAL_QUANTITY, VALUE_TO_REPLENISH));
+    for coffee_maker_config in &config.coffee_makers {
+        coffee_makers.push(CoffeeMaker::with_clock(
+            coffee_maker_config.clone(),
+            clock.clone(),
+        ));
     }
 
     coffee_makers
 }
 
-fn main() -> Result<(), Error> {
-    let input_controller = InputController::new(std::env::args().nth(1))?;
-    let orders_list = input_controller.get_orders()?;
+/// Returns the arguments that are not flags nor values of a flag.
+fn get_inputs(args: &[String]) -> Vec<String> {
+    let mut inputs = Vec::new();
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else {
+            inputs.push(arg.clone());
+        }
+    }
+
+    inputs
+}
+
+/// Returns the value that follows the flag received in the arguments, if any.
+fn get_flag_value(args: &[String], flag: &str) -> Option<String> {
+    let idx = args.iter().position(|arg| arg == flag)?;
+    args.get(idx + 1).cloned()
+}
+
+/// Returns the configuration of the file received with the config flag,
+/// or the default configuration if there is not one.
+fn get_config(args: &[String]) -> Result<Config, Error> {
+    match get_flag_value(args, CONFIG_FLAG) {
+        Some(filename) => Config::from_file(&filename),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Returns the rate of orders per second received with the arrival rate flag, if any.
+fn get_arrival_rate(args: &[String]) -> Result<Option<f64>, Error> {
+    let Some(rate) = get_flag_value(args, ARRIVAL_RATE_FLAG) else {
+        return Ok(None);
+    };
+
+    match rate.parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0.0 => Ok(Some(value)),
+        _ => Err(Error::WrongArrivalRate(rate)),
+    }
+}
+
+/// Returns the clock received with the clock flag, real or virtual, or a real clock if
+/// there is not one. A real clock runs faster by the factor received with the speed-up flag.
+fn get_clock(args: &[String]) -> Result<Clock, Error> {
+    let speed_up = match get_flag_value(args, SPEED_UP_FLAG) {
+        Some(speed_up) => match speed_up.parse::<f64>() {
+            Ok(value) if value.is_finite() && value > 0.0 => value,
+            _ => return Err(Error::WrongClock(speed_up)),
+        },
+        None => 1.0,
+    };
+
+    match get_flag_value(args, CLOCK_FLAG).as_deref() {
+        None | Some(REAL_CLOCK) => Ok(Clock::with_speed_up(speed_up)),
+        Some(VIRTUAL_CLOCK) => Ok(Clock::simulated()),
+        Some(clock) => Err(Error::WrongClock(clock.to_owned())),
+    }
+}
+
+/// Returns the queue of orders of the input.
+/// If the input is a stream of orders, they are read by a thread that feeds the queue
+/// while the coffee makers are already working, and its handle is also returned. The
+/// queue holds every order until its arrival time, and with an arrival rate the orders
+/// without one get it as they are read.
+/// If the orders have arrival times, or an arrival rate is received, a thread feeds the
+/// queue with every order at its arrival time, and its handle is also returned.
+/// The time the orders wait in the queue is measured with the clock received.
+fn get_orders(
+    input_controller: InputController,
+    arrival_rate: Option<f64>,
+    clock: &Clock,
+) -> Result<(Arc<OrdersQueue>, Option<Reader>), Error> {
+    if input_controller.is_stream() {
+        let mut orders = OrdersQueue::with_capacity(STREAM_CAPACITY).with_clock(clock.clone());
+        if let Some(rate) = arrival_rate {
+            orders = orders.with_arrival_rate(rate, ArrivalGenerator::from_time());
+        }
+        let orders = Arc::new(orders);
+        let orders_clone = orders.clone();
+        let reader = thread::spawn(move || input_controller.stream_orders(orders_clone));
+        println!("STREAMING ORDERS TO PROCESS");
+        return Ok((orders, Some(reader)));
+    }
+
+    let mut orders_list = input_controller.get_orders()?;
     println!("TOTAL ORDERS TO PROCESS: {:?}", orders_list.len());
+    if let Some(rate) = arrival_rate {
+        set_poisson_arrivals(&mut orders_list, rate, &mut ArrivalGenerator::from_time());
+    }
+    if has_arrivals(&orders_list) {
+        let orders = Arc::new(OrdersQueue::new().with_clock(clock.clone()));
+        let orders_clone = orders.clone();
+        let feeder_clock = clock.clone();
+        let feeder = thread::spawn(move || feed_orders(orders_list, orders_clone, feeder_clock));
+        println!("FEEDING ORDERS AT THEIR ARRIVAL TIME");
+        return Ok((orders, Some(feeder)));
+    }
+
+    Ok((
+        Arc::new(OrdersQueue::from_orders(orders_list).with_clock(clock.clone())),
+        None,
+    ))
+}
+
+/// Starts to apply to the coffee makers the commands of the control file received, that
+/// can be a named pipe where an operator writes while the coffee makers are working.
+/// The commands are read and applied by threads that are not joined, since the operator
+/// may not close the pipe.
+fn start_control(path: &str, coffee_makers: Vec<CoffeeMaker>) -> Result<(), Error> {
+    let Ok(file) = File::open(path) else {
+        return Err(Error::ControlFileNotFound);
+    };
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || read_commands(BufReader::new(file), sender));
+    thread::spawn(move || handle_commands(receiver, coffee_makers));
+    println!("[CONTROL]: READING COMMANDS FROM {}", path);
+
+    Ok(())
+}
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().collect();
+    let config = get_config(&args)?;
+    let mut input_controller = InputController::new(get_inputs(&args))?;
+    if let Some(format) = get_flag_value(&args, FORMAT_FLAG) {
+        input_controller.format = Some(OrdersFormat::from_name(&format)?);
+    }
+    input_controller.validator = OrderValidator::from_config(&config);
+    let arrival_rate = get_arrival_rate(&args)?;
+    let clock = get_clock(&args)?;
+    let (orders, reader) = get_orders(input_controller, arrival_rate, &clock)?;
 
-    let orders = Arc::new(RwLock::new(orders_list));
     let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
-    let coffee_makers = get_coffee_makers();
+    let mut coffee_makers = get_coffee_makers(&config, &clock);
+    if let Some(path) = get_flag_value(&args, LOAD_STATE_FLAG) {
+        Snapshot::from_file(&path)?.restore(&mut coffee_makers)?;
+        println!("[SNAPSHOT]: RESTORED FROM {}", path);
+    }
+    if let Some(path) = get_flag_value(&args, CONTROL_FLAG) {
+        start_control(&path, coffee_makers.clone())?;
+    }
+    let report = Report::with_clock(clock.clone());
 
     let mut machines: Vec<JoinHandle<Result<(), Error>>> = Vec::new();
     for coffee_maker in coffee_makers.clone() {
         let orders = orders.clone();
         let orders_processed = orders_processed.clone();
         let coffee_maker_clone = coffee_maker.clone();
+        let report = report.clone();
         let handle: JoinHandle<Result<(), Error>> = thread::spawn(move || {
             coffee_maker_clone
                 .clone()
-                .start(&orders, orders_processed)?;
+                .start(&orders, orders_processed, report)?;
             Ok(())
         });
         machines.push(handle);
     }
 
-    show_statistics(coffee_makers, orders_processed, orders)?;
+    show_statistics(
+        coffee_makers.clone(),
+        orders_processed,
+        orders,
+        report.clone(),
+        clock,
+    )?;
 
     for handle in machines {
         match handle.join() {
@@ -52,17 +234,53 @@ fn main() -> Result<(), Error> {
         }
     }
 
-    Ok(())
+    let mut result = Ok(());
+    if let Some(reader) = reader {
+        result = match reader.join() {
+            Ok(result) => result,
+            Err(err) => {
+                println!("[READER]: ERROR {:?} WHEN JOINING", err);
+                Ok(())
+            }
+        };
+        if let Err(err) = &result {
+            println!("[READER]: {:?} WHEN READING ORDERS", err);
+        }
+    }
+
+    if let Some(path) = get_flag_value(&args, REPORT_FLAG) {
+        report.write_to(&path)?;
+        println!("[REPORT]: WRITTEN TO {}", path);
+    }
+
+    if let Some(path) = get_flag_value(&args, SAVE_STATE_FLAG) {
+        Snapshot::from_coffee_makers(&coffee_makers)?.write_to(&path)?;
+        println!("[SNAPSHOT]: WRITTEN TO {}", path);
+    }
+
+    if let Some(path) = get_flag_value(&args, AUDIT_LOG_FLAG) {
+        AuditLog::from_coffee_makers(&coffee_makers)?.write_to(&path)?;
+        println!("[AUDIT LOG]: WRITTEN TO {}", path);
+    }
+
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use std::{
-        sync::{Arc, Condvar, Mutex, RwLock},
+        sync::{Arc, Condvar, Mutex},
         thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    use tp1::{
+        clock::Clock, coffee_maker::CoffeeMaker, config::CoffeeMakerConfig,
+        ingredients::Ingredient, input_controller::InputController, orders::Order,
+        orders_queue::OrdersQueue, report::Report,
     };
 
-    use tp1::{coffee_maker::CoffeeMaker, orders::Order};
+    use crate::get_orders;
 
     #[test]
     fn test01_two_coffee_makers_with_two_dispensers_that_make_three_orders() {
@@ -75,9 +293,12 @@ mod tests {
 
         let mut coffee_makers = Vec::new();
         for j in 0..2 {
-            coffee_makers.push(CoffeeMaker::new(j, 100, 50));
+            coffee_makers.push(CoffeeMaker::with_clock(
+                CoffeeMakerConfig::new(j, 100, 50),
+                Clock::simulated(),
+            ));
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
 
         let mut machines: Vec<JoinHandle<()>> = Vec::new();
         for coffee_maker in coffee_makers.clone() {
@@ -85,7 +306,7 @@ mod tests {
             let orders_processed = orders_processed.clone();
             let handle = thread::spawn(move || {
                 let coffee_maker_clone = coffee_maker.clone();
-                match coffee_maker_clone.start(&orders, orders_processed) {
+                match coffee_maker_clone.start(&orders, orders_processed, Report::new()) {
                     Ok(_) => println!("[COFFEE MAKER {:?}]: FINISHING", coffee_maker.id),
                     Err(err) => {
                         println!("[COFFEE MAKER {:?}]: {:?} ERROR", coffee_maker.id, err)
@@ -104,36 +325,36 @@ mod tests {
 
         let coffee_0 = coffee_maker_0
             .containers
-            .get_quantity_of(&"coffee".to_string())
+            .get_quantity_of(&Ingredient::Coffee)
             .expect("Error when locking coffee container");
         let foam_0 = coffee_maker_0
             .containers
-            .get_quantity_of(&"foam".to_string())
+            .get_quantity_of(&Ingredient::Foam)
             .expect("Error when locking foam container");
         let water_0 = coffee_maker_0
             .containers
-            .get_quantity_of(&"hot_water".to_string())
+            .get_quantity_of(&Ingredient::HotWater)
             .expect("Error when locking hot water container");
         let cocoa_0 = coffee_maker_0
             .containers
-            .get_quantity_of(&"cocoa".to_string())
+            .get_quantity_of(&Ingredient::Cocoa)
             .expect("Error when locking cocoa container");
 
         let coffee_1 = coffee_maker_1
             .containers
-            .get_quantity_of(&"coffee".to_string())
+            .get_quantity_of(&Ingredient::Coffee)
             .expect("Error when locking coffee container");
         let foam_1 = coffee_maker_1
             .containers
-            .get_quantity_of(&"foam".to_string())
+            .get_quantity_of(&Ingredient::Foam)
             .expect("Error when locking foam container");
         let water_1 = coffee_maker_1
             .containers
-            .get_quantity_of(&"hot_water".to_string())
+            .get_quantity_of(&Ingredient::HotWater)
             .expect("Error when locking hot water container");
         let cocoa_1 = coffee_maker_1
             .containers
-            .get_quantity_of(&"cocoa".to_string())
+            .get_quantity_of(&Ingredient::Cocoa)
             .expect("Error when locking cocoa container");
 
         assert_ne!(coffee_0, coffee_1);
@@ -141,4 +362,65 @@ mod tests {
         assert_ne!(cocoa_0, cocoa_1);
         assert_ne!(foam_0, foam_1);
     }
+
+    #[test]
+    fn test02_stream_a_csv_file_with_arrival_times() {
+        let input_controller = InputController::new(vec!["resources/orders09.csv".to_string()])
+            .expect("The filename is invalid");
+        let clock = Clock::simulated();
+        let (orders, reader) =
+            get_orders(input_controller, None, &clock).expect("The orders are invalid");
+        let coffee_maker = CoffeeMaker::with_clock(
+            CoffeeMakerConfig {
+                dispensers: 1,
+                ..CoffeeMakerConfig::new(0, 100, 50)
+            },
+            clock.clone(),
+        );
+        let report = Report::with_clock(clock);
+
+        coffee_maker
+            .start(
+                &orders,
+                Arc::new((Mutex::new(0), Condvar::new())),
+                report.clone(),
+            )
+            .expect("Error when making the orders");
+        reader
+            .expect("The orders are streamed")
+            .join()
+            .expect("Error when joining")
+            .expect("Error when reading the orders");
+
+        let outcomes = report
+            .get_outcomes()
+            .expect("Error when locking the report");
+        let times: Vec<(u32, u64, u64)> = outcomes
+            .iter()
+            .map(|outcome| (outcome.order_id, outcome.start_ms, outcome.queue_wait_ms))
+            .collect();
+        assert_eq!(times, vec![(0, 0, 0), (1, 4000, 3000), (2, 20000, 0)]);
+        assert_eq!(report.get_queue_wait_ms(), Ok((1000, 3000)));
+    }
+
+    #[test]
+    fn test03_load_a_json_file_with_arrival_times_with_the_virtual_clock_without_waiting() {
+        let input_controller = InputController::new(vec!["resources/orders08.json".to_string()])
+            .expect("The filename is invalid");
+        let start = Instant::now();
+        let (orders, feeder) = get_orders(input_controller, None, &Clock::simulated())
+            .expect("The orders are invalid");
+        let mut ids = Vec::new();
+        while let Ok(order) = orders.pop() {
+            ids.push(order.id);
+        }
+        feeder
+            .expect("The orders are fed at their arrival time")
+            .join()
+            .expect("Error when joining")
+            .expect("Error when feeding the orders");
+
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(ids, (0..10).collect::<Vec<u32>>());
+    }
 }
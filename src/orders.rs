@@ -1,21 +1,180 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
-#[derive(Deserialize, Debug, Clone)]
+use crate::{
+    errors::Error,
+    ingredients::{Ingredient, IngredientRegistry, WATER},
+    recipes::RecipeCatalog,
+    units::Quantity,
+};
+
+/// An order with the quantity of every ingredient it needs, in the unit of its container.
+/// The ingredients that are not in the order are not needed.
+/// The orders with a higher priority are served first, the default priority is zero.
+/// An order with an arrival time is not available until that time, in milliseconds since
+/// the start, and once it is taken it knows how long it waited in the queue.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(try_from = "HashMap<String, OrderQuantity>")]
 pub struct Order {
-    pub coffee: u32,
-    pub water: u32,
-    pub cocoa: u32,
-    pub foam: u32,
+    pub id: u32,
+    pub priority: u32,
+    pub arrival_ms: Option<u64>,
+    pub queue_wait_ms: u64,
+    pub ingredients: HashMap<String, u32>,
 }
 
 impl Order {
-    /// Creates a new [`Order`].
+    /// Creates a new [`Order`] of coffee, water, cocoa and foam.
     pub fn new(coffee_value: u32, water_value: u32, cocoa_value: u32, foam_value: u32) -> Order {
+        let mut ingredients = HashMap::new();
+        ingredients.insert(Ingredient::Coffee.name().to_owned(), coffee_value);
+        ingredients.insert(WATER.to_owned(), water_value);
+        ingredients.insert(Ingredient::Cocoa.name().to_owned(), cocoa_value);
+        ingredients.insert(Ingredient::Foam.name().to_owned(), foam_value);
+
+        Order::from(ingredients)
+    }
+
+    /// Returns the same [`Order`] with the id received.
+    pub fn with_id(mut self, id_value: u32) -> Order {
+        self.id = id_value;
+        self
+    }
+
+    /// Returns the same [`Order`] with the priority received.
+    pub fn with_priority(mut self, priority_value: u32) -> Order {
+        self.priority = priority_value;
+        self
+    }
+
+    /// Returns the same [`Order`] with the arrival time received.
+    pub fn with_arrival_ms(mut self, arrival_ms_value: u64) -> Order {
+        self.arrival_ms = Some(arrival_ms_value);
+        self
+    }
+
+    /// Returns the quantity of the ingredient in the order, zero if it is not in it.
+    pub fn get(&self, ingredient: &str) -> u32 {
+        self.ingredients
+            .get(ingredient)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the quantity to take from every container of the registry received to make
+    /// the order, sorted by ingredient. Returns an error if an ingredient of the order is
+    /// unknown.
+    pub fn get_containers(
+        &self,
+        registry: &IngredientRegistry,
+    ) -> Result<Vec<(Ingredient, u32)>, Error> {
+        let mut containers: HashMap<Ingredient, u32> = HashMap::new();
+        for (ingredient, quantity) in &self.ingredients {
+            *containers
+                .entry(registry.get_container_of(ingredient)?)
+                .or_default() += quantity;
+        }
+
+        let mut containers: Vec<(Ingredient, u32)> = containers.into_iter().collect();
+        containers.sort();
+        Ok(containers)
+    }
+}
+
+impl TryFrom<HashMap<String, OrderQuantity>> for Order {
+    type Error = String;
+
+    /// Converts the quantities with a unit to the unit of the containers of the base
+    /// ingredients, where the ingredients added in the configuration are measured in grams.
+    fn try_from(quantities: HashMap<String, OrderQuantity>) -> Result<Self, Self::Error> {
+        let registry = IngredientRegistry::new();
+        let mut ingredients = HashMap::new();
+        for (ingredient, quantity) in quantities {
+            match quantity.get_value(&ingredient, &registry) {
+                Ok(value) => ingredients.insert(ingredient, value),
+                Err(err) => return Err(format!("{:?}", err)),
+            };
+        }
+
+        Ok(Order::from(ingredients))
+    }
+}
+
+impl From<HashMap<String, u32>> for Order {
+    fn from(ingredients: HashMap<String, u32>) -> Self {
         Order {
-            coffee: coffee_value,
-            water: water_value,
-            cocoa: cocoa_value,
-            foam: foam_value,
+            id: 0,
+            priority: 0,
+            arrival_ms: None,
+            queue_wait_ms: 0,
+            ingredients,
         }
     }
 }
+
+/// The quantity of an ingredient as it is written in an order: either a value in the unit
+/// of its container or a text with its own unit, like "1 shot".
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OrderQuantity {
+    Value(u32),
+    WithUnit(String),
+}
+
+impl OrderQuantity {
+    /// Returns the quantity of the ingredient of the order received in the unit of its
+    /// container in the registry received, returns an error if it is not a quantity or
+    /// can't be converted.
+    pub fn get_value(&self, ingredient: &str, registry: &IngredientRegistry) -> Result<u32, Error> {
+        match self {
+            OrderQuantity::Value(value) => Ok(*value),
+            OrderQuantity::WithUnit(text) => registry.convert(
+                &Ingredient::from_order_name(ingredient),
+                &Quantity::parse(text)?,
+            ),
+        }
+    }
+}
+
+/// The content of an order: either the name of a recipe of the catalog or the quantity
+/// of every ingredient.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OrderKind {
+    Recipe { recipe: String },
+    Ingredients(Order),
+}
+
+/// An order as it is written in the orders file, with an optional id, priority and
+/// arrival time.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OrderRequest {
+    #[serde(default)]
+    pub id: Option<u32>,
+    #[serde(default)]
+    pub priority: u32,
+    #[serde(default)]
+    pub arrival_ms: Option<u64>,
+    #[serde(flatten)]
+    pub kind: OrderKind,
+}
+
+impl OrderRequest {
+    /// Returns the order with the quantities of its ingredients, resolving its recipe
+    /// from the catalog if it has one.
+    /// The order gets the id of the request or the default id received if it has not one.
+    pub fn resolve(self, recipes: &RecipeCatalog, default_id: u32) -> Result<Order, Error> {
+        let order = match self.kind {
+            OrderKind::Recipe { recipe } => recipes.get_order(&recipe)?,
+            OrderKind::Ingredients(order) => order,
+        };
+
+        let mut order = order
+            .with_id(self.id.unwrap_or(default_id))
+            .with_priority(self.priority);
+        order.arrival_ms = self.arrival_ms;
+
+        Ok(order)
+    }
+}
@@ -1,37 +1,47 @@
 pub mod order_handler {
-    use std::sync::{Arc, Condvar, Mutex, RwLock};
+    use std::{
+        sync::{Arc, Condvar, Mutex},
+        time::Duration,
+    };
 
     use crate::{
-        coffee_maker::CoffeeMaker, dispensers::dispenser::make_order, errors::Error, orders::Order,
+        clock::Clock,
+        coffee_maker::CoffeeMaker,
+        dispensers::dispenser::make_order,
+        errors::Error,
+        orders::Order,
+        orders_queue::OrdersQueue,
+        report::{OrderOutcome, OrderStatus, Report},
     };
 
-    const IDX_COFFEE: u32 = 0;
-    const IDX_WATER: u32 = 1;
-    const IDX_FOAM: u32 = 2;
+    const REPLENISH_TIMEOUT: Duration = Duration::from_secs(10);
 
-    /// Notifies to replenish an ingredient.
+    /// Notifies to replenish every ingredient.
     pub fn notify_to_replenish(has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>, value: bool) {
         let (has_to_replenish_lock, condvar) = &*has_to_replenish;
         if let Ok(mut has_to_replenish) = has_to_replenish_lock.lock() {
-            has_to_replenish[IDX_COFFEE as usize] = value;
-            has_to_replenish[IDX_FOAM as usize] = value;
-            has_to_replenish[IDX_WATER as usize] = value;
+            has_to_replenish.iter_mut().for_each(|flag| *flag = value);
         }
         condvar.notify_all();
     }
 
-    /// Notifies to alert that the quantity of containers is less than a quarter in
-    /// order to finalize a thread.
+    /// Notifies to check the alerts of every container in order to finalize a thread.
     pub fn notify_to_alert(has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>) {
         let (has_to_alert_lock, condvar) = &*has_to_alert;
         if let Ok(mut has_to_alert) = has_to_alert_lock.lock() {
-            has_to_alert[IDX_COFFEE as usize] = true;
-            has_to_alert[IDX_FOAM as usize] = true;
-            has_to_alert[IDX_WATER as usize] = true;
+            has_to_alert.iter_mut().for_each(|flag| *flag = true);
         }
         condvar.notify_all();
     }
 
+    /// Notifies the presenter waiting for more orders to be processed that there are no more
+    /// orders to make.
+    pub fn notify_no_more_orders(orders_processed: Arc<(Mutex<i32>, Condvar)>) {
+        let (orders_processed_lock, condvar) = &*orders_processed;
+        let _orders_processed = orders_processed_lock.lock();
+        condvar.notify_all();
+    }
+
     /// Notifies to replenish an ingredient.
     pub fn notify_to_replenish_ingredient(
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
@@ -46,41 +56,104 @@ pub mod order_handler {
         condvar.notify_all();
     }
 
-    /// Gets an order from the list of orders if there are more orders to make,
+    /// Waits until the ingredient was replenished, or the replenish timeout of the clock
+    /// received passed if the ingredient handler is not replenishing any more.
+    /// A virtual clock doesn't move while it waits, so it waits until the handler answers,
+    /// which it always does while the dispensers are working.
+    pub fn wait_for_replenish(
+        has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
+        idx: u32,
+        clock: &Clock,
+    ) {
+        let (has_to_replenish_lock, condvar) = &*has_to_replenish;
+        let Ok(has_to_replenish) = has_to_replenish_lock.lock() else {
+            return;
+        };
+        if clock.is_virtual() {
+            drop(condvar.wait_while(has_to_replenish, |v| v[idx as usize]));
+        } else {
+            drop(condvar.wait_timeout_while(
+                has_to_replenish,
+                clock.get_real_duration(REPLENISH_TIMEOUT),
+                |v| v[idx as usize],
+            ));
+        }
+    }
+
+    /// Gets an order from the queue of orders if there are more orders to make,
     /// returns an error if not.
+    /// While the queue is empty but more orders can arrive, it waits for them.
     fn get_order(
-        orders: Arc<RwLock<Vec<Order>>>,
+        orders: Arc<OrdersQueue>,
+        orders_processed: Arc<(Mutex<i32>, Condvar)>,
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
         has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
     ) -> Result<Order, Error> {
-        let order = if let Ok(mut orders) = orders.write() {
-            if !orders.is_empty() {
-                orders.remove(0)
-            } else {
+        match orders.pop() {
+            Ok(order) => Ok(order),
+            Err(Error::NoMoreOrders) => {
+                notify_no_more_orders(orders_processed);
                 notify_to_replenish(has_to_replenish, true);
                 notify_to_alert(has_to_alert);
-                return Err(Error::NoMoreOrders);
+                Err(Error::NoMoreOrders)
             }
-        } else {
-            return Err(Error::CantWriteOrdersLock);
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Adds the outcome of the order to the report with the result of making it and the
+    /// quantities it needed in the units of the containers of the coffee maker.
+    fn add_outcome(
+        report: &Report,
+        order: &Order,
+        coffee_maker: &CoffeeMaker,
+        dispenser_id: u32,
+        start_ms: u64,
+        result: &Result<(), Error>,
+    ) -> Result<(), Error> {
+        let (status, reason) = match result {
+            Ok(_) => (OrderStatus::Completed, None),
+            Err(err) => (OrderStatus::Failed, Some(format!("{:?}", err))),
         };
+        let quantities = order
+            .get_containers(&coffee_maker.registry)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(ingredient, value)| {
+                let quantity = coffee_maker.registry.get_quantity(&ingredient, value);
+                (ingredient, quantity)
+            })
+            .collect();
 
-        Ok(order)
+        report.add(OrderOutcome {
+            order_id: order.id,
+            coffee_maker_id: coffee_maker.id,
+            dispenser_id,
+            queue_wait_ms: order.queue_wait_ms,
+            start_ms,
+            end_ms: report.elapsed_ms(),
+            status,
+            reason,
+            quantities,
+        })
     }
 
     /// Gets an order and processes it if it can,
     /// returns an error if not.
+    /// The outcome of every order taken is added to the report.
     pub fn process_order(
-        orders: Arc<RwLock<Vec<Order>>>,
+        orders: Arc<OrdersQueue>,
         coffee_maker: CoffeeMaker,
         dispenser_id: u32,
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
         has_to_replenish: Arc<(Mutex<Vec<bool>>, Condvar)>,
         has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
+        report: Report,
     ) -> Result<(), Error> {
         loop {
             match get_order(
                 orders.clone(),
+                orders_processed.clone(),
                 has_to_replenish.clone(),
                 has_to_alert.clone(),
             ) {
@@ -89,22 +162,34 @@ pub mod order_handler {
                         "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: MAKING {:?}",
                         dispenser_id, coffee_maker.id, order
                     );
-                    match make_order(
-                        order,
+                    let order_id = order.id;
+                    let start_ms = report.elapsed_ms();
+                    let result = make_order(
+                        order.clone(),
                         coffee_maker.clone(),
                         dispenser_id,
                         orders_processed.clone(),
                         has_to_replenish.clone(),
-                    ) {
+                        has_to_alert.clone(),
+                    );
+                    add_outcome(
+                        &report,
+                        &order,
+                        &coffee_maker,
+                        dispenser_id,
+                        start_ms,
+                        &result,
+                    )?;
+                    match result {
                         Ok(_) => println!(
-                            "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: FINISHING ORDER",
-                            dispenser_id, coffee_maker.id
+                            "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: FINISHING ORDER {:?}",
+                            dispenser_id, coffee_maker.id, order_id
                         ),
                         Err(err) => match err {
-                            Error::NotEnoughIngredient => {
+                            Error::NotEnoughIngredient | Error::UnknownIngredient(_) => {
                                 println!(
-                                    "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: {:?}",
-                                    dispenser_id, coffee_maker.id, err
+                                    "[DISPENSER {:?}] OF [COFFEE MAKER {:?}]: {:?} IN ORDER {:?}",
+                                    dispenser_id, coffee_maker.id, err, order_id
                                 );
                                 continue;
                             }
@@ -116,4 +201,45 @@ pub mod order_handler {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{
+            sync::{Arc, Condvar, Mutex},
+            thread,
+            time::Instant,
+        };
+
+        use crate::{clock::Clock, orders_handler::order_handler::wait_for_replenish};
+
+        #[test]
+        fn test01_with_a_virtual_clock_the_dispenser_waits_for_the_handler_without_a_timeout() {
+            let clock = Clock::simulated();
+            let has_to_replenish = Arc::new((Mutex::new(vec![true]), Condvar::new()));
+            let handler_flags = has_to_replenish.clone();
+            let handler = thread::spawn(move || {
+                let (has_to_replenish_lock, condvar) = &*handler_flags;
+                if let Ok(mut has_to_replenish) = has_to_replenish_lock.lock() {
+                    has_to_replenish[0] = false;
+                }
+                condvar.notify_all();
+            });
+            wait_for_replenish(has_to_replenish.clone(), 0, &clock);
+            handler.join().expect("Error when joining");
+
+            assert_eq!(clock.now_ms(), 0);
+            assert_eq!(has_to_replenish.0.lock().map(|v| v[0]).ok(), Some(false));
+        }
+
+        #[test]
+        fn test02_the_replenish_timeout_runs_faster_with_the_speed_up_of_the_clock() {
+            let clock = Clock::with_speed_up(100.0);
+            let has_to_replenish = Arc::new((Mutex::new(vec![true]), Condvar::new()));
+            let start = Instant::now();
+            wait_for_replenish(has_to_replenish, 0, &clock);
+
+            assert!(clock.now_ms() >= 10_000);
+            assert!(start.elapsed().as_secs() < 1);
+        }
+    }
 }
@@ -0,0 +1,462 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+use crate::{clock::Clock, errors::Error, feeder::ArrivalGenerator, orders::Order};
+
+const STARVATION_LIMIT: u64 = 20;
+
+/// An order waiting in the queue with the number of orders that had been taken from
+/// the queue when it arrived and the time it is released: the time it was pushed or its
+/// arrival time, if it is later.
+#[derive(Debug)]
+struct QueuedOrder {
+    order: Order,
+    arrival: u64,
+    taken_before: u64,
+    released_ms: u64,
+}
+
+/// The orders waiting in the queue grouped by priority, every priority in order of
+/// release. The orders without arrival time can get one from the arrival rate.
+#[derive(Debug, Default)]
+struct QueueState {
+    priorities: BTreeMap<u32, VecDeque<QueuedOrder>>,
+    len: usize,
+    arrived: u64,
+    taken: u64,
+    closed: bool,
+    arrival_rate: Option<(f64, ArrivalGenerator)>,
+    last_arrival_ms: u64,
+}
+
+impl QueueState {
+    /// Adds an order pushed at the time received after the orders of its same priority
+    /// that are released until its arrival time, or until that time if it has not one.
+    fn push(&mut self, mut order: Order, pushed_ms: u64) {
+        if let (None, Some((rate, generator))) = (order.arrival_ms, &mut self.arrival_rate) {
+            self.last_arrival_ms += generator.next_gap_ms(*rate);
+            order.arrival_ms = Some(self.last_arrival_ms);
+        }
+        let queued_order = QueuedOrder {
+            arrival: self.arrived,
+            taken_before: self.taken,
+            released_ms: pushed_ms.max(order.arrival_ms.unwrap_or_default()),
+            order,
+        };
+        let orders = self
+            .priorities
+            .entry(queued_order.order.priority)
+            .or_default();
+        let idx = orders.partition_point(|other| other.released_ms <= queued_order.released_ms);
+        orders.insert(idx, queued_order);
+        self.arrived += 1;
+        self.len += 1;
+    }
+
+    /// Returns the first order of every priority that is released until the time received.
+    fn get_released_until(&self, now_ms: u64) -> impl Iterator<Item = (u32, &QueuedOrder)> {
+        self.priorities
+            .iter()
+            .filter_map(|(priority, orders)| Some((*priority, orders.front()?)))
+            .filter(move |(_, queued)| queued.released_ms <= now_ms)
+    }
+
+    /// Returns the milliseconds until the next order is released, if there are orders and
+    /// none of them is released at the time received.
+    fn get_release_wait_ms(&self, now_ms: u64) -> Option<u64> {
+        if self.get_released_until(now_ms).next().is_some() {
+            return None;
+        }
+
+        self.get_released_until(u64::MAX)
+            .map(|(_, queued)| queued.released_ms - now_ms)
+            .min()
+    }
+
+    /// Returns the priority of the oldest order that has waited while the starvation limit
+    /// of orders were taken before it, if there is one.
+    fn get_starving_priority(&self, starvation_limit: u64, now_ms: u64) -> Option<u32> {
+        self.get_released_until(now_ms)
+            .filter(|(_, queued)| self.taken - queued.taken_before >= starvation_limit)
+            .min_by_key(|(_, queued)| queued.arrival)
+            .map(|(priority, _)| priority)
+    }
+
+    /// Returns the priority of the next order to take: the oldest starving order, or the
+    /// highest priority. Only a virtual clock can take an order released later than its
+    /// time, when there is no other, and then it takes the first one that is released.
+    fn get_next_priority(&self, starvation_limit: u64, now_ms: u64) -> Option<u32> {
+        if let Some(priority) = self.get_starving_priority(starvation_limit, now_ms) {
+            return Some(priority);
+        }
+        if let Some((priority, _)) = self.get_released_until(now_ms).last() {
+            return Some(priority);
+        }
+
+        self.get_released_until(u64::MAX)
+            .min_by_key(|(_, queued)| (queued.released_ms, queued.arrival))
+            .map(|(priority, _)| priority)
+    }
+
+    /// Takes the next order, with the time it waited in the queue since it was released
+    /// measured with the clock received. A virtual clock catches up with the time the order
+    /// was released.
+    fn pop(&mut self, starvation_limit: u64, clock: &Clock) -> Option<Order> {
+        let now_ms = clock.now_ms();
+        let priority = self.get_next_priority(starvation_limit, now_ms)?;
+        let orders = self.priorities.get_mut(&priority)?;
+        let queued_order = orders.pop_front()?;
+        if orders.is_empty() {
+            self.priorities.remove(&priority);
+        }
+        self.taken += 1;
+        self.len -= 1;
+
+        let mut order = queued_order.order;
+        order.queue_wait_ms = now_ms.saturating_sub(queued_order.released_ms);
+        clock.advance_to_ms(queued_order.released_ms);
+        Some(order)
+    }
+}
+
+/// Queue of orders shared between the dispensers of all the coffee machines.
+/// Orders can be pushed while the dispensers are taking them until the queue is closed.
+/// The orders with a higher priority are taken first and the orders with the same
+/// priority are taken in the order they were pushed. An order that waited while too many
+/// orders were taken before it is taken next, whatever its priority. An order with an
+/// arrival time is held in the queue until that time.
+#[derive(Debug)]
+pub struct OrdersQueue {
+    state: Mutex<QueueState>,
+    condvar: Condvar,
+    capacity: Option<usize>,
+    starvation_limit: u64,
+    clock: Clock,
+}
+
+impl OrdersQueue {
+    /// Creates a new open [`OrdersQueue`] without a limit of orders.
+    pub fn new() -> OrdersQueue {
+        OrdersQueue {
+            state: Mutex::new(QueueState::default()),
+            condvar: Condvar::new(),
+            capacity: None,
+            starvation_limit: STARVATION_LIMIT,
+            clock: Clock::real(),
+        }
+    }
+
+    /// Creates a new open [`OrdersQueue`] that holds at most the capacity received,
+    /// pushing an order to a full queue waits until a dispenser takes one.
+    pub fn with_capacity(capacity: usize) -> OrdersQueue {
+        OrdersQueue {
+            capacity: Some(capacity.max(1)),
+            ..OrdersQueue::new()
+        }
+    }
+
+    /// Creates a new closed [`OrdersQueue`] with all the orders received, that are there
+    /// since the start of its clock.
+    pub fn from_orders(orders: Vec<Order>) -> OrdersQueue {
+        let mut state = QueueState {
+            closed: true,
+            ..QueueState::default()
+        };
+        for order in orders {
+            state.push(order, 0);
+        }
+
+        OrdersQueue {
+            state: Mutex::new(state),
+            ..OrdersQueue::new()
+        }
+    }
+
+    /// Returns the same [`OrdersQueue`] where an order is taken next, whatever its priority,
+    /// once the number of orders received were taken while it was waiting.
+    pub fn with_starvation_limit(mut self, starvation_limit: u64) -> OrdersQueue {
+        self.starvation_limit = starvation_limit.max(1);
+        self
+    }
+
+    /// Returns the same [`OrdersQueue`] where the orders without arrival time get the
+    /// arrival times of customers that arrive at the rate of orders per second received,
+    /// in the order they are pushed.
+    pub fn with_arrival_rate(mut self, rate: f64, generator: ArrivalGenerator) -> OrdersQueue {
+        if let Ok(state) = self.state.get_mut() {
+            state.arrival_rate = Some((rate, generator));
+        }
+        self
+    }
+
+    /// Returns the same [`OrdersQueue`] where the time the orders wait is measured with the
+    /// clock received.
+    pub fn with_clock(mut self, clock: Clock) -> OrdersQueue {
+        self.clock = clock;
+        self
+    }
+
+    /// Adds an order after the orders of its same priority, waiting if the queue is full.
+    pub fn push(&self, order: Order) -> Result<(), Error> {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Err(Error::CantWriteOrdersLock),
+        };
+        let capacity = self.capacity.unwrap_or(usize::MAX);
+        if let Ok(mut state) = self
+            .condvar
+            .wait_while(state, |s| s.len >= capacity && !s.closed)
+        {
+            state.push(order, self.clock.now_ms());
+        } else {
+            return Err(Error::CantWriteOrdersLock);
+        }
+        self.condvar.notify_all();
+
+        Ok(())
+    }
+
+    /// Closes the queue, so no more orders are going to be pushed.
+    pub fn close(&self) -> Result<(), Error> {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+        } else {
+            return Err(Error::CantWriteOrdersLock);
+        }
+        self.condvar.notify_all();
+
+        Ok(())
+    }
+
+    /// Takes the next order of the queue, waiting while the queue is empty but open and,
+    /// with a real clock, until an order is released.
+    /// Returns an error if the queue is empty and closed.
+    pub fn pop(&self) -> Result<Order, Error> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Err(Error::CantWriteOrdersLock),
+        };
+        let order = loop {
+            state = match self.condvar.wait_while(state, |s| s.len == 0 && !s.closed) {
+                Ok(state) => state,
+                Err(_) => return Err(Error::CantWriteOrdersLock),
+            };
+            match state.get_release_wait_ms(self.clock.now_ms()) {
+                Some(wait_ms) if !self.clock.is_virtual() => {
+                    let timeout = self.clock.get_real_duration(Duration::from_millis(wait_ms));
+                    state = match self.condvar.wait_timeout(state, timeout) {
+                        Ok((state, _)) => state,
+                        Err(_) => return Err(Error::CantWriteOrdersLock),
+                    };
+                }
+                _ => break state.pop(self.starvation_limit, &self.clock),
+            }
+        };
+        drop(state);
+        self.condvar.notify_all();
+
+        order.ok_or(Error::NoMoreOrders)
+    }
+
+    /// Returns the number of orders waiting in the queue.
+    pub fn len(&self) -> usize {
+        match self.state.lock() {
+            Ok(state) => state.len,
+            Err(_) => 0,
+        }
+    }
+
+    /// Returns true if there are no orders waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the queue is closed and there are no more orders to take.
+    pub fn is_finished(&self) -> bool {
+        match self.state.lock() {
+            Ok(state) => state.closed && state.len == 0,
+            Err(_) => true,
+        }
+    }
+}
+
+impl Default for OrdersQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use crate::{
+        clock::Clock, errors::Error, feeder::ArrivalGenerator, orders::Order,
+        orders_queue::OrdersQueue,
+    };
+
+    #[test]
+    fn test01_take_the_orders_in_the_same_order_they_were_pushed() {
+        let queue = OrdersQueue::new();
+        queue
+            .push(Order::new(1, 1, 1, 1).with_id(0))
+            .expect("Error when pushing");
+        queue
+            .push(Order::new(1, 1, 1, 1).with_id(1))
+            .expect("Error when pushing");
+        queue.close().expect("Error when closing");
+
+        assert_eq!(queue.pop().expect("There are orders").id, 0);
+        assert_eq!(queue.pop().expect("There are orders").id, 1);
+    }
+
+    #[test]
+    fn test02_take_an_order_from_a_closed_and_empty_queue() {
+        let queue = OrdersQueue::from_orders(Vec::new());
+        let result = queue.pop().expect_err("There are no more orders");
+
+        assert_eq!(result, Error::NoMoreOrders);
+        assert!(queue.is_finished());
+    }
+
+    #[test]
+    fn test03_an_open_queue_is_not_finished_although_it_is_empty() {
+        let queue = OrdersQueue::new();
+
+        assert!(queue.is_empty());
+        assert!(!queue.is_finished());
+    }
+
+    #[test]
+    fn test04_take_an_order_waits_until_one_is_pushed() {
+        let queue = Arc::new(OrdersQueue::with_capacity(1));
+        let consumer_queue = queue.clone();
+        let consumer = thread::spawn(move || {
+            let mut ids = Vec::new();
+            while let Ok(order) = consumer_queue.pop() {
+                ids.push(order.id);
+            }
+            ids
+        });
+
+        for id in 0..5 {
+            queue
+                .push(Order::new(1, 1, 1, 1).with_id(id))
+                .expect("Error when pushing");
+            assert!(queue.len() <= 1);
+        }
+        queue.close().expect("Error when closing");
+
+        let ids = consumer.join().expect("Error when joining");
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test05_take_the_orders_with_higher_priority_first() {
+        let orders = vec![
+            Order::new(1, 1, 1, 1).with_id(0),
+            Order::new(1, 1, 1, 1).with_id(1).with_priority(2),
+            Order::new(1, 1, 1, 1).with_id(2).with_priority(1),
+            Order::new(1, 1, 1, 1).with_id(3).with_priority(2),
+            Order::new(1, 1, 1, 1).with_id(4),
+        ];
+        let queue = OrdersQueue::from_orders(orders);
+
+        let mut ids = Vec::new();
+        while let Ok(order) = queue.pop() {
+            ids.push(order.id);
+        }
+        assert_eq!(ids, vec![1, 3, 2, 0, 4]);
+    }
+
+    #[test]
+    fn test06_an_order_with_low_priority_is_not_starved() {
+        let queue = OrdersQueue::new().with_starvation_limit(2);
+        queue
+            .push(Order::new(1, 1, 1, 1).with_id(0))
+            .expect("Error when pushing");
+        for id in 1..6 {
+            queue
+                .push(Order::new(1, 1, 1, 1).with_id(id).with_priority(1))
+                .expect("Error when pushing");
+        }
+        queue.close().expect("Error when closing");
+
+        let mut ids = Vec::new();
+        while let Ok(order) = queue.pop() {
+            ids.push(order.id);
+        }
+        assert_eq!(ids, vec![1, 2, 0, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test07_with_a_virtual_clock_an_order_is_not_taken_before_it_is_pushed() {
+        let clock = Clock::simulated();
+        let queue = Arc::new(OrdersQueue::new().with_clock(clock.clone()));
+        let pusher_queue = queue.clone();
+        let pusher_clock = clock.clone();
+        thread::spawn(move || {
+            pusher_clock.advance_to_ms(100);
+            pusher_queue.push(Order::new(1, 1, 1, 1).with_id(0))?;
+            pusher_clock.advance_to_ms(200);
+            pusher_queue.push(Order::new(1, 1, 1, 1).with_id(1).with_priority(5))?;
+            pusher_queue.close()
+        })
+        .join()
+        .expect("Error when joining")
+        .expect("Error when pushing");
+
+        clock.sleep(Duration::from_millis(150));
+        let first = queue.pop().expect("There are orders");
+        assert_eq!((first.id, first.queue_wait_ms), (0, 50));
+        let second = queue.pop().expect("There are orders");
+        assert_eq!((second.id, second.queue_wait_ms), (1, 0));
+        assert_eq!(clock.now_ms(), 200);
+    }
+
+    #[test]
+    fn test08_an_order_is_held_in_the_queue_until_its_arrival_time() {
+        let clock = Clock::with_speed_up(100.0);
+        let queue = OrdersQueue::new().with_clock(clock.clone());
+        queue
+            .push(Order::new(1, 1, 1, 1).with_id(0).with_arrival_ms(2000))
+            .expect("Error when pushing");
+        queue
+            .push(Order::new(1, 1, 1, 1).with_id(1).with_arrival_ms(1000))
+            .expect("Error when pushing");
+        queue.close().expect("Error when closing");
+
+        let first = queue.pop().expect("There are orders");
+        assert_eq!(first.id, 1);
+        assert!(clock.now_ms() >= 1000);
+        let second = queue.pop().expect("There are orders");
+        assert_eq!(second.id, 0);
+        assert!(clock.now_ms() >= 2000);
+    }
+
+    #[test]
+    fn test09_the_orders_without_arrival_time_get_one_from_the_arrival_rate() {
+        let clock = Clock::simulated();
+        let queue = OrdersQueue::new()
+            .with_clock(clock.clone())
+            .with_arrival_rate(10.0, ArrivalGenerator::new(7));
+        for id in 0..3 {
+            queue
+                .push(Order::new(1, 1, 1, 1).with_id(id))
+                .expect("Error when pushing");
+        }
+        queue.close().expect("Error when closing");
+
+        let mut last_arrival_ms = 0;
+        while let Ok(order) = queue.pop() {
+            let arrival_ms = order.arrival_ms.expect("The order has an arrival time");
+            assert!(arrival_ms >= last_arrival_ms);
+            assert_eq!(clock.now_ms(), arrival_ms);
+            last_arrival_ms = arrival_ms;
+        }
+        assert!(last_arrival_ms > 0);
+    }
+}
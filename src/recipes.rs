@@ -0,0 +1,105 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::{errors::Error, orders::Order};
+
+/// Catalog of named drinks with the quantity of every ingredient that each one needs.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RecipeCatalog {
+    recipes: HashMap<String, Order>,
+}
+
+impl RecipeCatalog {
+    /// Creates a new [`RecipeCatalog`] with the recipes received.
+    pub fn new(recipes: HashMap<String, Order>) -> RecipeCatalog {
+        RecipeCatalog { recipes }
+    }
+
+    /// Converts the recipes from a json string if it can,
+    /// returns an error if not.
+    pub fn deserialize(recipes: &str) -> Result<RecipeCatalog, Error> {
+        match serde_json::from_str::<HashMap<String, Order>>(recipes) {
+            Ok(recipes) => Ok(RecipeCatalog::new(recipes)),
+            Err(_) => Err(Error::WrongRecipesFormat),
+        }
+    }
+
+    /// Reads the recipes of the file received if it exists,
+    /// returns an empty catalog if not.
+    pub fn from_file(path: &Path) -> Result<RecipeCatalog, Error> {
+        if !path.exists() {
+            return Ok(RecipeCatalog::default());
+        }
+
+        match std::fs::read_to_string(path) {
+            Ok(recipes) => RecipeCatalog::deserialize(&recipes),
+            Err(_e) => Err(Error::WrongRecipesFormat),
+        }
+    }
+
+    /// Returns the order of the recipe received if it is in the catalog,
+    /// returns an error if not.
+    pub fn get_order(&self, recipe: &str) -> Result<Order, Error> {
+        match self.recipes.get(recipe) {
+            Some(order) => Ok(order.clone()),
+            None => Err(Error::UnknownRecipe(recipe.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{errors::Error, recipes::RecipeCatalog};
+
+    #[test]
+    fn test01_get_the_order_of_a_recipe_of_the_catalog() {
+        let recipes = "{\"latte\": {\"coffee\": 10, \"water\": 5, \"cocoa\": 0, \"foam\": 20}}";
+        let catalog = RecipeCatalog::deserialize(recipes).expect("The recipes are invalid");
+        let order = catalog.get_order("latte").expect("The recipe is unknown");
+
+        assert_eq!(order.get("coffee"), 10);
+        assert_eq!(order.get("water"), 5);
+        assert_eq!(order.get("cocoa"), 0);
+        assert_eq!(order.get("foam"), 20);
+    }
+
+    #[test]
+    fn test02_get_the_order_of_an_unknown_recipe() {
+        let catalog = RecipeCatalog::default();
+        let result = catalog
+            .get_order("frappuccino")
+            .expect_err("The recipe is unknown");
+
+        assert_eq!(result, Error::UnknownRecipe("frappuccino".to_string()));
+    }
+
+    #[test]
+    fn test03_get_recipes_with_wrong_format() {
+        let result = RecipeCatalog::deserialize("{\"latte\": {\"coffee\": \"ten\"}}")
+            .expect_err("The recipe has a quantity that is not a number");
+
+        assert_eq!(result, Error::WrongRecipesFormat);
+    }
+
+    #[test]
+    fn test04_get_recipes_of_a_not_found_file_returns_an_empty_catalog() {
+        let catalog = RecipeCatalog::from_file(Path::new("resources/recetas.json"))
+            .expect("The recipes are invalid");
+
+        assert!(catalog.get_order("espresso").is_err());
+    }
+
+    #[test]
+    fn test05_get_recipes_of_the_resources_catalog() {
+        let catalog = RecipeCatalog::from_file(Path::new("resources/recipes.json"))
+            .expect("The recipes are invalid");
+
+        assert!(catalog.get_order("espresso").is_ok());
+        assert!(catalog.get_order("latte").is_ok());
+        assert!(catalog.get_order("cappuccino").is_ok());
+        assert!(catalog.get_order("mocha").is_ok());
+    }
+}
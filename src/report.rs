@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{clock::Clock, errors::Error, ingredients::Ingredient, units::Quantity};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Completed,
+    Failed,
+}
+
+/// What happened to an order: who took it, when, how it ended and the quantity of every
+/// container that it needed, with its unit.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OrderOutcome {
+    pub order_id: u32,
+    pub coffee_maker_id: u32,
+    pub dispenser_id: u32,
+    pub queue_wait_ms: u64,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub status: OrderStatus,
+    pub reason: Option<String>,
+    pub quantities: BTreeMap<Ingredient, Quantity>,
+}
+
+/// Outcomes of all the orders of a run, shared between all the dispensers of all the
+/// coffee machines.
+#[derive(Debug, Clone)]
+pub struct Report {
+    clock: Clock,
+    outcomes: Arc<Mutex<Vec<OrderOutcome>>>,
+}
+
+impl Report {
+    /// Creates a new [`Report`] whose times are measured from now.
+    pub fn new() -> Report {
+        Report::with_clock(Clock::real())
+    }
+
+    /// Creates a new [`Report`] whose times are measured with the clock received.
+    pub fn with_clock(clock: Clock) -> Report {
+        Report {
+            clock,
+            outcomes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns the milliseconds elapsed since the start of the clock of the report.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.now_ms()
+    }
+
+    /// Adds the outcome of an order to the report.
+    pub fn add(&self, outcome: OrderOutcome) -> Result<(), Error> {
+        if let Ok(mut outcomes) = self.outcomes.lock() {
+            outcomes.push(outcome);
+        } else {
+            return Err(Error::CantHaveReportLock);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the outcomes of the report ordered by the id of their orders.
+    pub fn get_outcomes(&self) -> Result<Vec<OrderOutcome>, Error> {
+        let mut outcomes = match self.outcomes.lock() {
+            Ok(outcomes) => outcomes.clone(),
+            Err(_) => return Err(Error::CantHaveReportLock),
+        };
+        outcomes.sort_by_key(|outcome| outcome.order_id);
+
+        Ok(outcomes)
+    }
+
+    /// Returns the average and the greatest time, in milliseconds, that the orders of the
+    /// report waited in the queue until a dispenser took them.
+    pub fn get_queue_wait_ms(&self) -> Result<(u64, u64), Error> {
+        let outcomes = self.get_outcomes()?;
+        if outcomes.is_empty() {
+            return Ok((0, 0));
+        }
+        let waits = outcomes.iter().map(|outcome| outcome.queue_wait_ms);
+        let total: u64 = waits.clone().sum();
+
+        Ok((
+            total / outcomes.len() as u64,
+            waits.max().unwrap_or_default(),
+        ))
+    }
+
+    /// Converts the outcomes of the report to a json string.
+    pub fn to_json(&self) -> Result<String, Error> {
+        match serde_json::to_string_pretty(&self.get_outcomes()?) {
+            Ok(json) => Ok(json),
+            Err(_) => Err(Error::CantWriteReport),
+        }
+    }
+
+    /// Writes the outcomes of the report as json to the path received.
+    pub fn write_to(&self, path: &str) -> Result<(), Error> {
+        match std::fs::write(path, self.to_json()?) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::CantWriteReport),
+        }
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        ingredients::Ingredient,
+        report::{OrderOutcome, OrderStatus, Report},
+        units::{Quantity, Unit},
+    };
+
+    fn get_outcome(order_id: u32, status: OrderStatus, reason: Option<String>) -> OrderOutcome {
+        OrderOutcome {
+            order_id,
+            coffee_maker_id: 0,
+            dispenser_id: 1,
+            queue_wait_ms: order_id as u64 * 10,
+            start_ms: 10,
+            end_ms: 20,
+            status,
+            reason,
+            quantities: BTreeMap::from([(Ingredient::Coffee, Quantity::new(7.0, Unit::Grams))]),
+        }
+    }
+
+    #[test]
+    fn test01_outcomes_are_ordered_by_order_id() {
+        let report = Report::new();
+        report
+            .add(get_outcome(2, OrderStatus::Completed, None))
+            .expect("Error when adding outcome");
+        report
+            .add(get_outcome(0, OrderStatus::Completed, None))
+            .expect("Error when adding outcome");
+
+        let outcomes = report.get_outcomes().expect("Error when getting outcomes");
+        assert_eq!(outcomes[0].order_id, 0);
+        assert_eq!(outcomes[1].order_id, 2);
+    }
+
+    #[test]
+    fn test02_report_is_converted_to_json() {
+        let report = Report::new();
+        report
+            .add(get_outcome(
+                3,
+                OrderStatus::Failed,
+                Some("NotEnoughIngredient".to_string()),
+            ))
+            .expect("Error when adding outcome");
+
+        let json = report.to_json().expect("Error when converting to json");
+        let value: serde_json::Value =
+            serde_json::from_str(&json).expect("The report is not valid json");
+        assert_eq!(value[0]["order_id"], 3);
+        assert_eq!(value[0]["dispenser_id"], 1);
+        assert_eq!(value[0]["status"], "failed");
+        assert_eq!(value[0]["reason"], "NotEnoughIngredient");
+        assert_eq!(value[0]["quantities"]["coffee"], "7 g");
+    }
+
+    #[test]
+    fn test03_get_the_average_and_greatest_queue_wait() {
+        let report = Report::new();
+        assert_eq!(report.get_queue_wait_ms(), Ok((0, 0)));
+        for order_id in 0..3 {
+            report
+                .add(get_outcome(order_id, OrderStatus::Completed, None))
+                .expect("Error when adding outcome");
+        }
+
+        assert_eq!(report.get_queue_wait_ms(), Ok((10, 20)));
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coffee_maker::CoffeeMaker, errors::Error, ingredients::Ingredient};
+
+/// State of a container: its quantity, how many times and how much it was replenished and
+/// how much of it expired.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ContainerSnapshot {
+    pub quantity: u32,
+    pub replenishments: u32,
+    pub replenished: u32,
+    pub wasted: u32,
+}
+
+/// State of a coffee maker: its containers and the alerts that are still pending.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CoffeeMakerSnapshot {
+    pub id: u32,
+    pub containers: BTreeMap<Ingredient, ContainerSnapshot>,
+    pub pending_alerts: Vec<Ingredient>,
+}
+
+/// State of all the coffee makers, that can be saved at the end of a run and restored
+/// at the start of the next one.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub coffee_makers: Vec<CoffeeMakerSnapshot>,
+}
+
+impl Snapshot {
+    /// Takes a new [`Snapshot`] of the current state of the coffee makers received.
+    pub fn from_coffee_makers(coffee_makers: &[CoffeeMaker]) -> Result<Snapshot, Error> {
+        let mut snapshots = Vec::new();
+        for coffee_maker in coffee_makers {
+            snapshots.push(coffee_maker.get_snapshot()?);
+        }
+
+        Ok(Snapshot {
+            coffee_makers: snapshots,
+        })
+    }
+
+    /// Restores the state of every coffee maker of the snapshot in the coffee maker with
+    /// its same id. The coffee makers that are not in the snapshot keep their state.
+    /// Returns an error if a coffee maker of the snapshot is not one of the coffee makers.
+    pub fn restore(&self, coffee_makers: &mut [CoffeeMaker]) -> Result<(), Error> {
+        for snapshot in &self.coffee_makers {
+            match coffee_makers
+                .iter_mut()
+                .find(|coffee_maker| coffee_maker.id == snapshot.id)
+            {
+                Some(coffee_maker) => coffee_maker.restore(snapshot)?,
+                None => return Err(Error::WrongSnapshotFormat),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts the snapshot from a json string if it can, returns an error if not.
+    pub fn deserialize(snapshot: &str) -> Result<Snapshot, Error> {
+        match serde_json::from_str::<Snapshot>(snapshot) {
+            Ok(snapshot) => Ok(snapshot),
+            Err(_) => Err(Error::WrongSnapshotFormat),
+        }
+    }
+
+    /// Reads the snapshot of the path received if it can, returns an error if not.
+    pub fn from_file(path: &str) -> Result<Snapshot, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(snapshot) => Snapshot::deserialize(&snapshot),
+            Err(_) => Err(Error::SnapshotNotFound),
+        }
+    }
+
+    /// Converts the snapshot to a json string.
+    pub fn to_json(&self) -> Result<String, Error> {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => Ok(json),
+            Err(_) => Err(Error::CantWriteSnapshot),
+        }
+    }
+
+    /// Writes the snapshot as json to the path received.
+    pub fn write_to(&self, path: &str) -> Result<(), Error> {
+        match std::fs::write(path, self.to_json()?) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::CantWriteSnapshot),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::Clock,
+        coffee_maker::CoffeeMaker,
+        config::CoffeeMakerConfig,
+        errors::Error,
+        ingredients::Ingredient,
+        snapshot::{ContainerSnapshot, Snapshot},
+    };
+
+    fn get_coffee_maker(id: u32) -> CoffeeMaker {
+        CoffeeMaker::with_clock(CoffeeMakerConfig::new(id, 100, 50), Clock::simulated())
+    }
+
+    #[test]
+    fn test01_take_a_snapshot_and_convert_it_to_json_and_back() {
+        let coffee_maker = get_coffee_maker(0);
+        coffee_maker
+            .containers
+            .reserve(&[(Ingredient::Coffee, 100), (Ingredient::Milk, 80)], 0, 0)
+            .expect("There are enough ingredients");
+        coffee_maker
+            .containers
+            .clone()
+            .replenish_ingredient(&Ingredient::Coffee, 30, 0)
+            .expect("Error when replenishing");
+
+        let snapshot = Snapshot::from_coffee_makers(&[coffee_maker]).expect("Error when taking");
+        let coffee_maker_snapshot = &snapshot.coffee_makers[0];
+        assert_eq!(
+            coffee_maker_snapshot.containers[&Ingredient::Coffee],
+            ContainerSnapshot {
+                quantity: 30,
+                replenishments: 1,
+                replenished: 30,
+                wasted: 0,
+            }
+        );
+        assert_eq!(
+            coffee_maker_snapshot.containers[&Ingredient::Milk].quantity,
+            20
+        );
+        assert_eq!(coffee_maker_snapshot.pending_alerts, vec![Ingredient::Milk]);
+
+        let json = snapshot.to_json().expect("Error when converting to json");
+        assert_eq!(Snapshot::deserialize(&json), Ok(snapshot));
+    }
+
+    #[test]
+    fn test02_restore_a_snapshot_keeps_the_capacity_of_the_containers() {
+        let snapshot = "{\"coffee_makers\": [{\"id\": 1, \"containers\": {\"coffee\": {\"quantity\": 20, \"replenishments\": 3, \"replenished\": 150}}, \"pending_alerts\": [\"milk\"]}]}";
+        let snapshot = Snapshot::deserialize(snapshot).expect("The snapshot is valid");
+        let mut coffee_makers = vec![get_coffee_maker(0), get_coffee_maker(1)];
+        snapshot
+            .restore(&mut coffee_makers)
+            .expect("Error when restoring");
+
+        let restored = &coffee_makers[1];
+        assert_eq!(
+            restored.containers.get_quantity_of(&Ingredient::Coffee),
+            Ok(20)
+        );
+        assert_eq!(
+            restored.containers.get_free_space_of(&Ingredient::Coffee),
+            Ok(80)
+        );
+        assert_eq!(
+            restored.config.get_initial_quantity(&Ingredient::Coffee),
+            20
+        );
+        assert_eq!(restored.config.get_capacity(&Ingredient::Coffee), 100);
+        assert_eq!(restored.pending_alerts, vec![Ingredient::Milk]);
+        let restored_snapshot = restored.get_snapshot().expect("Error when taking");
+        assert_eq!(
+            restored_snapshot.containers[&Ingredient::Coffee].replenishments,
+            3
+        );
+        assert_eq!(
+            coffee_makers[0]
+                .containers
+                .get_quantity_of(&Ingredient::Coffee),
+            Ok(100)
+        );
+    }
+
+    #[test]
+    fn test03_restore_a_snapshot_of_other_coffee_makers_returns_an_error() {
+        let mut coffee_makers = vec![get_coffee_maker(0)];
+        let other = Snapshot::deserialize("{\"coffee_makers\": [{\"id\": 5}]}")
+            .expect("The snapshot is valid");
+        let unknown = Snapshot::deserialize(
+            "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"tea\": {\"quantity\": 1}}}]}",
+        )
+        .expect("The snapshot is valid");
+
+        assert_eq!(
+            other.restore(&mut coffee_makers),
+            Err(Error::WrongSnapshotFormat)
+        );
+        assert_eq!(
+            unknown.restore(&mut coffee_makers),
+            Err(Error::UnknownIngredient("tea".to_string()))
+        );
+        assert_eq!(
+            Snapshot::from_file("resources/snapshot.json"),
+            Err(Error::SnapshotNotFound)
+        );
+    }
+}
@@ -1,37 +1,19 @@
 pub mod stats_maker {
 
-    use std::collections::HashMap;
+    use std::collections::{BTreeSet, HashMap};
 
-    use crate::{coffee_maker::CoffeeMaker, containers::Containers};
-
-    const COFFEE: &str = "coffee";
-    const FOAM: &str = "foam";
-    const HOT_WATER: &str = "hot_water";
-    const COCOA: &str = "cocoa";
-
-    const GRAIN_COFFEE: &str = "grain_coffee";
-    const MILK: &str = "milk";
-    const COLD_WATER: &str = "cold_water";
-
-    const INITIAL_QUANTITY_WATER: u32 = 1000;
-
-    const INGREDIENTS: [&str; 7] = [
-        COFFEE,
-        HOT_WATER,
-        COCOA,
-        FOAM,
-        GRAIN_COFFEE,
-        MILK,
-        COLD_WATER,
-    ];
-    const INGREDIENTS_TO_REPLENISH: [&str; 3] = [COFFEE, FOAM, HOT_WATER];
-    const RESOURCE_INGREDIENTS: [&str; 3] = [GRAIN_COFFEE, MILK, COLD_WATER];
+    use crate::{
+        coffee_maker::CoffeeMaker,
+        containers::Containers,
+        ingredients::{Ingredient, REPLENISHABLE_INGREDIENTS},
+        units::Unit,
+    };
 
     /// Gets the current quantity of all the containers of all the coffee machines.
-    pub fn get_quantity_of(containers: Containers) -> HashMap<String, u32> {
+    pub fn get_quantity_of(containers: Containers) -> HashMap<Ingredient, u32> {
         let mut level_of_containers = HashMap::new();
-        for ingredient in INGREDIENTS {
-            if let Ok(quantity) = containers.clone().get_quantity_of(&ingredient.to_owned()) {
+        for ingredient in containers.all.keys() {
+            if let Ok(quantity) = containers.get_quantity_of(ingredient) {
                 level_of_containers.insert(ingredient.to_owned(), quantity);
             }
         }
@@ -42,7 +24,7 @@ pub mod stats_maker {
     /// Returns a list of hashmaps. Every element of the list represents a coffee machine
     /// and every hashmap represents its container where the key is the ingredient of it and
     /// the value is its quantity.
-    pub fn get_containers_info(coffee_makers: Vec<CoffeeMaker>) -> Vec<HashMap<String, u32>> {
+    pub fn get_containers_info(coffee_makers: Vec<CoffeeMaker>) -> Vec<HashMap<Ingredient, u32>> {
         let mut vec = Vec::new();
         for coffee_maker in coffee_makers {
             vec.push(get_quantity_of(coffee_maker.containers));
@@ -51,114 +33,306 @@ pub mod stats_maker {
         vec
     }
 
+    /// Gets the percentage of the capacity that is filled of all the containers of a coffee
+    /// machine.
+    pub fn get_fill_percentages_of(containers: Containers) -> HashMap<Ingredient, u32> {
+        let mut fill_of_containers = HashMap::new();
+        for ingredient in containers.all.keys() {
+            if let Ok(percentage) = containers.get_fill_percentage_of(ingredient) {
+                fill_of_containers.insert(ingredient.to_owned(), percentage);
+            }
+        }
+
+        fill_of_containers
+    }
+
+    /// Returns a list of hashmaps. Every element of the list represents a coffee machine
+    /// and every hashmap represents the percentage of the capacity of its containers that
+    /// is filled.
+    pub fn get_containers_fill_info(
+        coffee_makers: Vec<CoffeeMaker>,
+    ) -> Vec<HashMap<Ingredient, u32>> {
+        let mut vec = Vec::new();
+        for coffee_maker in coffee_makers {
+            vec.push(get_fill_percentages_of(coffee_maker.containers));
+        }
+
+        vec
+    }
+
+    /// Gets the quantity that was discarded because it expired of all the containers of a
+    /// coffee machine.
+    pub fn get_wasted_of(containers: Containers) -> HashMap<Ingredient, u32> {
+        let mut waste_of_containers = HashMap::new();
+        for ingredient in containers.all.keys() {
+            if let Ok(wasted) = containers.get_wasted_of(ingredient) {
+                waste_of_containers.insert(ingredient.to_owned(), wasted);
+            }
+        }
+
+        waste_of_containers
+    }
+
+    /// Returns a list of hashmaps. Every element of the list represents a coffee machine
+    /// and every hashmap represents the quantity of its containers that expired.
+    pub fn get_containers_waste_info(
+        coffee_makers: Vec<CoffeeMaker>,
+    ) -> Vec<HashMap<Ingredient, u32>> {
+        let mut vec = Vec::new();
+        for coffee_maker in coffee_makers {
+            vec.push(get_wasted_of(coffee_maker.containers));
+        }
+
+        vec
+    }
+
+    /// Returns a list of hashmaps. Every element of the list represents a coffee machine
+    /// and every hashmap represents the initial quantity of its containers.
+    pub fn get_initial_levels(coffee_makers: Vec<CoffeeMaker>) -> Vec<HashMap<Ingredient, u32>> {
+        let mut vec = Vec::new();
+        for coffee_maker in coffee_makers {
+            let mut initial_levels = HashMap::new();
+            for ingredient in coffee_maker.config.get_ingredients() {
+                let initial_quantity = coffee_maker.config.get_initial_quantity(&ingredient);
+                initial_levels.insert(ingredient, initial_quantity);
+            }
+            vec.push(initial_levels);
+        }
+
+        vec
+    }
+
+    /// Returns how many times the dispensers of every coffee machine had to wait for an
+    /// ingredient to be replenished.
+    pub fn get_replenish_waits(coffee_makers: Vec<CoffeeMaker>) -> Vec<u32> {
+        coffee_makers
+            .iter()
+            .map(|coffee_maker| coffee_maker.handler.get_waits())
+            .collect()
+    }
+
+    /// Returns a list of hashmaps. Every element of the list represents a coffee machine
+    /// and every hashmap represents how much of every ingredient its ingredient handler
+    /// replenished.
+    pub fn get_replenished_info(coffee_makers: Vec<CoffeeMaker>) -> Vec<HashMap<Ingredient, u32>> {
+        coffee_makers
+            .iter()
+            .map(|coffee_maker| coffee_maker.handler.get_replenished())
+            .collect()
+    }
+
+    /// Returns the unit of the containers of every ingredient of any of the coffee
+    /// machines.
+    pub fn get_units(coffee_makers: Vec<CoffeeMaker>) -> HashMap<Ingredient, Unit> {
+        let mut units = HashMap::new();
+        for coffee_maker in coffee_makers {
+            for ingredient in coffee_maker.registry.get_all() {
+                let unit = coffee_maker.registry.get_unit(&ingredient);
+                units.entry(ingredient).or_insert(unit);
+            }
+        }
+
+        units
+    }
+
+    /// Returns the ingredients of the containers of any of the coffee machines.
+    fn get_ingredients(levels: &[HashMap<Ingredient, u32>]) -> BTreeSet<Ingredient> {
+        levels
+            .iter()
+            .flat_map(|level| level.keys().cloned())
+            .collect()
+    }
+
+    /// Adds up the quantity of every ingredient between all the coffee machines.
+    fn get_total_of(
+        levels: &[HashMap<Ingredient, u32>],
+        ingredients: &BTreeSet<Ingredient>,
+    ) -> HashMap<Ingredient, u32> {
+        let mut total = HashMap::new();
+        for ingredient in ingredients {
+            let mut quantity = 0;
+            for level in levels {
+                quantity += level.get(ingredient).copied().unwrap_or_default();
+            }
+            total.insert(ingredient.to_owned(), quantity);
+        }
+
+        total
+    }
+
+    /// Returns a list of hashmaps. Every element of the list represents a coffee machine
+    /// and every hashmap represents how much of every ingredient that is replenished by
+    /// default was replenished, taken as the quantity of its resource that was used.
+    fn get_replenished_by_default(
+        containers_level: &[HashMap<Ingredient, u32>],
+        initial_levels: &[HashMap<Ingredient, u32>],
+    ) -> Vec<HashMap<Ingredient, u32>> {
+        let mut vec = Vec::new();
+        for (initial, current) in initial_levels.iter().zip(containers_level) {
+            let mut replenished = HashMap::new();
+            for ingredient in REPLENISHABLE_INGREDIENTS {
+                let Some(resource) = ingredient.get_resource() else {
+                    continue;
+                };
+                let resource_used = get_quantity_consumed(
+                    initial.get(&resource).copied().unwrap_or_default(),
+                    0,
+                    current.get(&resource).copied().unwrap_or_default(),
+                );
+                replenished.insert(ingredient, resource_used);
+            }
+            vec.push(replenished);
+        }
+
+        vec
+    }
+
+    /// Updates the consumption of the ingredients that were replenished taking into
+    /// account the quantity of them that was replenished and the quantity of them that
+    /// was wasted.
     fn update_replenishing_ingredients(
-        mut ingredients_consumed: HashMap<String, u32>,
-    ) -> HashMap<String, u32> {
-        for (idx, ingredient) in INGREDIENTS_TO_REPLENISH.iter().enumerate() {
-            let resource = RESOURCE_INGREDIENTS[idx];
-            if ingredients_consumed[resource] > 0 {
-                let value = ingredients_consumed[ingredient.to_owned()];
-                let updated_value = value + ingredients_consumed[resource];
-                ingredients_consumed.insert(ingredient.to_string(), updated_value);
+        mut ingredients_consumed: HashMap<Ingredient, u32>,
+        initial: &HashMap<Ingredient, u32>,
+        current: &HashMap<Ingredient, u32>,
+        wasted: &HashMap<Ingredient, u32>,
+        replenished: &HashMap<Ingredient, u32>,
+    ) -> HashMap<Ingredient, u32> {
+        for (ingredient, replenished) in replenished {
+            if *replenished > 0 {
+                let updated_value = get_quantity_consumed(
+                    initial.get(ingredient).copied().unwrap_or_default(),
+                    *replenished,
+                    current.get(ingredient).copied().unwrap_or_default(),
+                )
+                .saturating_sub(wasted.get(ingredient).copied().unwrap_or_default());
+                ingredients_consumed.insert(ingredient.to_owned(), updated_value);
             };
         }
 
         ingredients_consumed
     }
 
-    /// Gets the consumed quantity if the ingredient received.
-    fn get_quantity_consumed(
-        ingredient: String,
-        current: u32,
-        containers_level: Vec<HashMap<String, u32>>,
-        initial_quantity: u32,
-    ) -> u32 {
-        let quantity: u32 = if ingredient == COLD_WATER {
-            (containers_level.len() as u32 * INITIAL_QUANTITY_WATER) - current
-        } else {
-            initial_quantity - current
-        };
-
-        quantity
+    /// Gets the consumed quantity of an ingredient from its initial quantity, the quantity
+    /// that was replenished and its current quantity.
+    fn get_quantity_consumed(initial: u32, replenished: u32, current: u32) -> u32 {
+        (initial + replenished).saturating_sub(current)
     }
 
     /// Shows the current quantity of ingredients consumed between all the containers of all the
-    /// coffee machines.
+    /// coffee machines, where the ingredients that are replenished by default were replenished
+    /// with the quantity of their resource that was used.
     pub fn get_ingredients_consumed(
-        containers_level: Vec<HashMap<String, u32>>,
-        initial_quantity: u32,
-    ) -> HashMap<String, u32> {
+        containers_level: Vec<HashMap<Ingredient, u32>>,
+        initial_levels: Vec<HashMap<Ingredient, u32>>,
+    ) -> HashMap<Ingredient, u32> {
+        let replenished = get_replenished_by_default(&containers_level, &initial_levels);
+        get_ingredients_consumed_with_waste(
+            containers_level,
+            initial_levels,
+            Vec::new(),
+            replenished,
+        )
+    }
+
+    /// Shows the current quantity of ingredients consumed between all the containers of all the
+    /// coffee machines, where the quantity that expired and was discarded is not consumed and
+    /// the quantity that was replenished of every ingredient, already yielded from its
+    /// resource, is added to its initial quantity.
+    pub fn get_ingredients_consumed_with_waste(
+        containers_level: Vec<HashMap<Ingredient, u32>>,
+        initial_levels: Vec<HashMap<Ingredient, u32>>,
+        containers_waste: Vec<HashMap<Ingredient, u32>>,
+        containers_replenished: Vec<HashMap<Ingredient, u32>>,
+    ) -> HashMap<Ingredient, u32> {
+        let mut ingredients = get_ingredients(&initial_levels);
+        ingredients.extend(get_ingredients(&containers_level));
+        let initial = get_total_of(&initial_levels, &ingredients);
+        let current = get_total_of(&containers_level, &ingredients);
+        let wasted = get_total_of(&containers_waste, &ingredients);
+        let replenished = get_total_of(&containers_replenished, &ingredients);
+
         let mut ingredients_consumed = HashMap::new();
-        for ingredient in INGREDIENTS {
-            let mut current = 0;
-            for i in containers_level.clone() {
-                current += i[&ingredient.to_owned()];
-            }
-            ingredients_consumed.insert(
-                ingredient.to_owned(),
-                get_quantity_consumed(
-                    ingredient.to_owned(),
-                    current,
-                    containers_level.clone(),
-                    initial_quantity,
-                ),
-            );
+        for ingredient in ingredients {
+            let quantity_consumed =
+                get_quantity_consumed(initial[&ingredient], 0, current[&ingredient])
+                    .saturating_sub(wasted[&ingredient]);
+            ingredients_consumed.insert(ingredient, quantity_consumed);
         }
 
-        update_replenishing_ingredients(ingredients_consumed)
+        update_replenishing_ingredients(
+            ingredients_consumed,
+            &initial,
+            &current,
+            &wasted,
+            &replenished,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{
-        sync::{Arc, Condvar, Mutex, RwLock},
+        collections::HashMap,
+        sync::{Arc, Condvar, Mutex},
         thread::{self, JoinHandle},
     };
 
     use crate::{
+        clock::Clock,
         coffee_maker::CoffeeMaker,
+        config::CoffeeMakerConfig,
+        ingredients::Ingredient,
         orders::Order,
-        stat_maker::stats_maker::{get_containers_info, get_ingredients_consumed},
+        orders_queue::OrdersQueue,
+        report::Report,
+        stat_maker::stats_maker::{
+            get_containers_fill_info, get_containers_info, get_containers_waste_info,
+            get_ingredients_consumed, get_ingredients_consumed_with_waste, get_initial_levels,
+            get_replenished_info, get_units,
+        },
+        units::Unit,
     };
 
     #[test]
     fn test01_get_one_coffee_maker_with_no_orders_processed() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
         let orders_list = Vec::new();
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
         let (orders_processed_lock, _condvar) = &*orders_processed;
         if let Ok(orders_processed) = orders_processed_lock.lock() {
             assert_eq!(*orders_processed, 0);
         }
         let containers_level_got = get_containers_info(coffee_makers);
-        assert_eq!(containers_level_got[0]["coffee"], 100);
-        assert_eq!(containers_level_got[0]["hot_water"], 100);
-        assert_eq!(containers_level_got[0]["foam"], 100);
-        assert_eq!(containers_level_got[0]["cocoa"], 100);
-        assert_eq!(containers_level_got[0]["grain_coffee"], 100);
-        assert_eq!(containers_level_got[0]["milk"], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Coffee], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::HotWater], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Foam], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Cocoa], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::GrainCoffee], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Milk], 100);
     }
 
     #[test]
     fn test02_get_one_coffee_maker_with_one_order_and_the_level_of_its_container_is_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
-        let mut orders_list = Vec::new();
-        orders_list.push(Order::new(10, 10, 5, 5));
-        let orders = Arc::new(RwLock::new(orders_list));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
+        let orders_list = vec![Order::new(10, 10, 5, 5)];
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
 
         let (orders_processed_lock, _condvar) = &*orders_processed;
@@ -166,29 +340,31 @@ mod tests {
             assert_eq!(*orders_processed, 1);
         }
         let containers_level_got = get_containers_info(coffee_makers);
-        assert_eq!(containers_level_got[0]["coffee"], 90);
-        assert_eq!(containers_level_got[0]["hot_water"], 90);
-        assert_eq!(containers_level_got[0]["foam"], 95);
-        assert_eq!(containers_level_got[0]["cocoa"], 95);
-        assert_eq!(containers_level_got[0]["grain_coffee"], 100);
-        assert_eq!(containers_level_got[0]["milk"], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Coffee], 90);
+        assert_eq!(containers_level_got[0][&Ingredient::HotWater], 90);
+        assert_eq!(containers_level_got[0][&Ingredient::Foam], 95);
+        assert_eq!(containers_level_got[0][&Ingredient::Cocoa], 95);
+        assert_eq!(containers_level_got[0][&Ingredient::GrainCoffee], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Milk], 100);
     }
 
     #[test]
     fn test03_get_one_coffee_maker_with_five_orders_and_the_level_of_its_container_is_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
         let mut orders_list = Vec::new();
         let order = Order::new(10, 10, 5, 5);
         for _ in 0..5 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
 
         let (orders_processed_lock, _condvar) = &*orders_processed;
@@ -196,29 +372,31 @@ mod tests {
             assert_eq!(*orders_processed, 5);
         }
         let containers_level_got = get_containers_info(coffee_makers);
-        assert_eq!(containers_level_got[0]["coffee"], 50);
-        assert_eq!(containers_level_got[0]["hot_water"], 50);
-        assert_eq!(containers_level_got[0]["foam"], 75);
-        assert_eq!(containers_level_got[0]["cocoa"], 75);
-        assert_eq!(containers_level_got[0]["grain_coffee"], 100);
-        assert_eq!(containers_level_got[0]["milk"], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Coffee], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::HotWater], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::Foam], 75);
+        assert_eq!(containers_level_got[0][&Ingredient::Cocoa], 75);
+        assert_eq!(containers_level_got[0][&Ingredient::GrainCoffee], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Milk], 100);
     }
 
     #[test]
     fn test04_get_one_coffee_maker_with_ten_orders_and_the_level_of_its_container_is_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
         let mut orders_list = Vec::new();
         let order = Order::new(10, 10, 5, 5);
         for _ in 0..10 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
 
         let (orders_processed_lock, _condvar) = &*orders_processed;
@@ -226,101 +404,110 @@ mod tests {
             assert_eq!(*orders_processed, 10);
         }
         let containers_level_got = get_containers_info(coffee_makers);
-        assert_eq!(containers_level_got[0]["coffee"], 50);
-        assert_eq!(containers_level_got[0]["foam"], 50);
-        assert_eq!(containers_level_got[0]["cocoa"], 50);
-        assert_eq!(containers_level_got[0]["grain_coffee"], 50);
-        assert_eq!(containers_level_got[0]["milk"], 100);
+        assert_eq!(containers_level_got[0][&Ingredient::Coffee], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::Foam], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::Cocoa], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::GrainCoffee], 50);
+        assert_eq!(containers_level_got[0][&Ingredient::Milk], 100);
     }
 
     #[test]
     fn test05_get_one_coffee_maker_with_no_orders_and_ingredients_consumed_are_zero() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
         let orders_list = Vec::new();
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
         let (orders_processed_lock, _condvar) = &*orders_processed;
         if let Ok(orders_processed) = orders_processed_lock.lock() {
             assert_eq!(*orders_processed, 0);
         }
+        let initial_levels = get_initial_levels(coffee_makers.clone());
         let containers_level = get_containers_info(coffee_makers);
-        let ingredients_consumed_got = get_ingredients_consumed(containers_level, 100);
-        assert_eq!(ingredients_consumed_got["coffee"], 0);
-        assert_eq!(ingredients_consumed_got["hot_water"], 0);
-        assert_eq!(ingredients_consumed_got["foam"], 0);
-        assert_eq!(ingredients_consumed_got["cocoa"], 0);
-        assert_eq!(ingredients_consumed_got["grain_coffee"], 0);
-        assert_eq!(ingredients_consumed_got["milk"], 0);
+        let ingredients_consumed_got = get_ingredients_consumed(containers_level, initial_levels);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::HotWater], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Foam], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Cocoa], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::GrainCoffee], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Milk], 0);
     }
 
     #[test]
     fn test06_get_one_coffee_maker_with_one_order_processed_and_ingredients_consumed_are_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
-        let mut orders_list = Vec::new();
-        orders_list.push(Order::new(10, 10, 5, 5));
-        let orders = Arc::new(RwLock::new(orders_list));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
+        let orders_list = vec![Order::new(10, 10, 5, 5)];
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
 
+        let initial_levels = get_initial_levels(coffee_makers.clone());
         let containers_level = get_containers_info(coffee_makers);
-        let ingredients_consumed_got = get_ingredients_consumed(containers_level, 100);
-        assert_eq!(ingredients_consumed_got["coffee"], 10);
-        assert_eq!(ingredients_consumed_got["hot_water"], 10);
-        assert_eq!(ingredients_consumed_got["foam"], 5);
-        assert_eq!(ingredients_consumed_got["cocoa"], 5);
-        assert_eq!(ingredients_consumed_got["grain_coffee"], 0);
-        assert_eq!(ingredients_consumed_got["milk"], 0);
+        let ingredients_consumed_got = get_ingredients_consumed(containers_level, initial_levels);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 10);
+        assert_eq!(ingredients_consumed_got[&Ingredient::HotWater], 10);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Foam], 5);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Cocoa], 5);
+        assert_eq!(ingredients_consumed_got[&Ingredient::GrainCoffee], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Milk], 0);
     }
 
     #[test]
     fn test07_get_one_coffee_maker_with_five_orders_and_ingredients_consumed_are_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
+        let coffee_makers = vec![CoffeeMaker::with_clock(
+            CoffeeMakerConfig::new(0, 100, 50),
+            Clock::simulated(),
+        )];
         let mut orders_list = Vec::new();
         let order = Order::new(10, 10, 5, 5);
         for _ in 0..5 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let coffee_maker = coffee_makers[0].clone();
         coffee_maker
-            .start(&orders, orders_processed.clone())
+            .start(&orders, orders_processed.clone(), Report::new())
             .expect("Error when coffee machine 0 process order");
 
+        let initial_levels = get_initial_levels(coffee_makers.clone());
         let containers_level = get_containers_info(coffee_makers);
-        let ingredients_consumed_got = get_ingredients_consumed(containers_level, 100);
-        assert_eq!(ingredients_consumed_got["coffee"], 50);
-        assert_eq!(ingredients_consumed_got["hot_water"], 50);
-        assert_eq!(ingredients_consumed_got["foam"], 25);
-        assert_eq!(ingredients_consumed_got["cocoa"], 25);
-        assert_eq!(ingredients_consumed_got["grain_coffee"], 0);
-        assert_eq!(ingredients_consumed_got["milk"], 0);
+        let ingredients_consumed_got = get_ingredients_consumed(containers_level, initial_levels);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 50);
+        assert_eq!(ingredients_consumed_got[&Ingredient::HotWater], 50);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Foam], 25);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Cocoa], 25);
+        assert_eq!(ingredients_consumed_got[&Ingredient::GrainCoffee], 0);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Milk], 0);
     }
 
     #[test]
     fn test08_get_two_coffee_makers_with_ten_orders_and_ingredients_consumed_are_updated() {
-        let mut coffee_makers = Vec::new();
-        coffee_makers.push(CoffeeMaker::new(0, 100, 50));
-        coffee_makers.push(CoffeeMaker::new(1, 100, 50));
+        let coffee_makers = vec![
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(0, 100, 50), Clock::simulated()),
+            CoffeeMaker::with_clock(CoffeeMakerConfig::new(1, 100, 50), Clock::simulated()),
+        ];
         let mut orders_list = Vec::new();
         let order = Order::new(10, 10, 5, 5);
         for _ in 0..10 {
             orders_list.push(order.clone());
         }
-        let orders = Arc::new(RwLock::new(orders_list));
+        let orders = Arc::new(OrdersQueue::from_orders(orders_list));
         let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
 
         let mut machines: Vec<JoinHandle<()>> = Vec::new();
@@ -329,7 +516,7 @@ mod tests {
             let orders_processed = orders_processed.clone();
             let handle = thread::spawn(move || {
                 let coffee_maker_clone = coffee_maker.clone();
-                match coffee_maker_clone.start(&orders, orders_processed) {
+                match coffee_maker_clone.start(&orders, orders_processed, Report::new()) {
                     Ok(_) => println!("[COFFEE MAKER {:?}]: FINISHING", coffee_maker.id),
                     Err(err) => {
                         println!("[COFFEE MAKER {:?}]: {:?} ERROR", coffee_maker.id, err)
@@ -343,11 +530,130 @@ mod tests {
             handle.join().expect("Error when joining");
         }
 
+        let initial_levels = get_initial_levels(coffee_makers.clone());
+        let containers_level = get_containers_info(coffee_makers);
+        let ingredients_consumed_got = get_ingredients_consumed(containers_level, initial_levels);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 100);
+        assert_eq!(ingredients_consumed_got[&Ingredient::HotWater], 100);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Foam], 50);
+        assert_eq!(ingredients_consumed_got[&Ingredient::Cocoa], 50);
+    }
+
+    #[test]
+    fn test09_get_the_consumption_of_a_container_added_in_the_configuration() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("sugar".to_string(), 30);
+        let coffee_makers = vec![CoffeeMaker::with_clock(config, Clock::simulated())];
+        let mut ingredients = HashMap::new();
+        ingredients.insert("coffee".to_string(), 10);
+        ingredients.insert("sugar".to_string(), 5);
+        let orders = Arc::new(OrdersQueue::from_orders(vec![Order::from(ingredients)]));
+        let orders_processed = Arc::new((Mutex::new(0), Condvar::new()));
+
+        coffee_makers[0]
+            .clone()
+            .start(&orders, orders_processed, Report::new())
+            .expect("Error when coffee machine 0 process order");
+
+        let initial_levels = get_initial_levels(coffee_makers.clone());
+        let containers_level = get_containers_info(coffee_makers);
+        assert_eq!(
+            containers_level[0][&Ingredient::Other("sugar".to_string())],
+            25
+        );
+        assert_eq!(containers_level[0][&Ingredient::HotWater], 100);
+        let ingredients_consumed_got = get_ingredients_consumed(containers_level, initial_levels);
+        assert_eq!(
+            ingredients_consumed_got[&Ingredient::Other("sugar".to_string())],
+            5
+        );
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 10);
+    }
+
+    #[test]
+    fn test10_get_the_fill_percentage_of_the_containers() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.capacities.insert("coffee".to_string(), 200);
+        let coffee_makers = vec![CoffeeMaker::with_clock(config, Clock::simulated())];
+
+        let fill_got = get_containers_fill_info(coffee_makers);
+        assert_eq!(fill_got[0][&Ingredient::Coffee], 50);
+        assert_eq!(fill_got[0][&Ingredient::Milk], 100);
+    }
+
+    #[test]
+    fn test11_get_the_unit_of_the_containers() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.containers.insert("tea".to_string(), 30);
+        config.units.insert("tea".to_string(), Unit::Milliliters);
+        let coffee_makers = vec![CoffeeMaker::with_clock(config, Clock::simulated())];
+
+        let units_got = get_units(coffee_makers);
+        assert_eq!(units_got[&Ingredient::Coffee], Unit::Grams);
+        assert_eq!(units_got[&Ingredient::Foam], Unit::Milliliters);
+        assert_eq!(
+            units_got[&Ingredient::Other("tea".to_string())],
+            Unit::Milliliters
+        );
+    }
+
+    #[test]
+    fn test12_the_quantity_that_expired_is_wasted_and_not_consumed() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.shelf_lives_ms.insert("milk".to_string(), 1000);
+        let coffee_makers = vec![CoffeeMaker::with_clock(config, Clock::simulated())];
+        let initial_levels = get_initial_levels(coffee_makers.clone());
+        coffee_makers[0]
+            .containers
+            .clone()
+            .get_ingredient(&Ingredient::Milk, 30, None, 0)
+            .expect("There is enough milk");
+        coffee_makers[0]
+            .containers
+            .discard_expired(0)
+            .expect("Error when discarding");
+
+        let containers_waste = get_containers_waste_info(coffee_makers.clone());
+        let containers_level = get_containers_info(coffee_makers);
+        assert_eq!(containers_waste[0][&Ingredient::Milk], 70);
+        assert_eq!(containers_level[0][&Ingredient::Milk], 0);
+        let ingredients_consumed_got = get_ingredients_consumed_with_waste(
+            containers_level,
+            initial_levels,
+            containers_waste,
+            Vec::new(),
+        );
+        assert_eq!(ingredients_consumed_got[&Ingredient::Milk], 30);
+    }
+
+    #[test]
+    fn test13_the_ingredients_replenished_are_consumed_with_the_yield_ratio_of_their_resource() {
+        let mut config = CoffeeMakerConfig::new(0, 100, 50);
+        config.yield_ratios.insert("coffee".to_string(), 0.8);
+        let coffee_makers = vec![CoffeeMaker::with_clock(config, Clock::simulated())];
+        let initial_levels = get_initial_levels(coffee_makers.clone());
+        coffee_makers[0]
+            .containers
+            .clone()
+            .get_ingredient(&Ingredient::Coffee, 100, None, 0)
+            .expect("There is enough coffee");
+        coffee_makers[0]
+            .handler
+            .clone()
+            .replenish(&Ingredient::Coffee)
+            .expect("Error when replenishing coffee");
+
+        let containers_replenished = get_replenished_info(coffee_makers.clone());
         let containers_level = get_containers_info(coffee_makers);
-        let ingredients_consumed_got = get_ingredients_consumed(containers_level, 2 * 100);
-        assert_eq!(ingredients_consumed_got["coffee"], 100);
-        assert_eq!(ingredients_consumed_got["hot_water"], 100);
-        assert_eq!(ingredients_consumed_got["foam"], 50);
-        assert_eq!(ingredients_consumed_got["cocoa"], 50);
+        assert_eq!(containers_level[0][&Ingredient::Coffee], 40);
+        assert_eq!(containers_level[0][&Ingredient::GrainCoffee], 50);
+        let ingredients_consumed_got = get_ingredients_consumed_with_waste(
+            containers_level,
+            initial_levels,
+            Vec::new(),
+            containers_replenished,
+        );
+        assert_eq!(ingredients_consumed_got[&Ingredient::Coffee], 100);
+        assert_eq!(ingredients_consumed_got[&Ingredient::GrainCoffee], 50);
     }
 }
@@ -2,41 +2,76 @@ pub mod presenter {
 
     use std::{
         collections::HashMap,
-        sync::{Arc, Condvar, Mutex, RwLock},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Condvar, Mutex,
+        },
         thread,
         time::Duration,
     };
 
     use crate::{
+        alerts::Alerter,
+        clock::Clock,
         coffee_maker::CoffeeMaker,
         containers::Containers,
         errors::Error,
-        orders::Order,
-        stat_maker::stats_maker::{get_containers_info, get_ingredients_consumed, get_quantity_of},
+        ingredients::Ingredient,
+        orders_queue::OrdersQueue,
+        report::Report,
+        stat_maker::stats_maker::{
+            get_containers_fill_info, get_containers_info, get_containers_waste_info,
+            get_ingredients_consumed_with_waste, get_initial_levels, get_replenish_waits,
+            get_replenished_info, get_units,
+        },
+        units::{Quantity, Unit},
     };
 
-    const INGREDIENTS: [&str; 6] = [
-        "coffee",
-        "hot_water",
-        "cocoa",
-        "foam",
-        "grain_coffee",
-        "milk",
-    ];
-    const INITIAL_QUANTITY: u32 = 100;
-    const VALUE_TO_ALERT: u32 = 50;
-
-    /// Shows the current quantity of all the containers of all the coffee machines.
+    const PRESENT_TIME: Duration = Duration::from_secs(3);
+
+    /// Returns the ingredients of a map sorted by name.
+    fn get_sorted_ingredients(quantities: &HashMap<Ingredient, u32>) -> Vec<&Ingredient> {
+        let mut ingredients: Vec<&Ingredient> = quantities.keys().collect();
+        ingredients.sort_by_key(|ingredient| ingredient.name());
+
+        ingredients
+    }
+
+    /// Returns the quantity received with the unit of the containers of the ingredient.
+    fn get_quantity(
+        units: &HashMap<Ingredient, Unit>,
+        ingredient: &Ingredient,
+        value: u32,
+    ) -> Quantity {
+        let unit = match units.get(ingredient) {
+            Some(unit) => *unit,
+            None => ingredient.get_unit(),
+        };
+
+        Quantity::new(value as f64, unit)
+    }
+
+    /// Shows the current quantity of all the containers of all the coffee machines, in the
+    /// unit of every container, with the percentage of their capacity that is filled.
     pub fn present_level_of_containers(
-        containers_level: Vec<HashMap<String, u32>>,
+        containers_level: Vec<HashMap<Ingredient, u32>>,
+        containers_fill: Vec<HashMap<Ingredient, u32>>,
+        units: &HashMap<Ingredient, Unit>,
     ) -> Result<(), Error> {
         println!("\n[LEVEL OF CONTAINERS]\n");
         for (id, containers) in containers_level.iter().enumerate() {
-            for ingredient in INGREDIENTS {
-                let quantity = containers[&ingredient.to_owned()];
+            for ingredient in get_sorted_ingredients(containers) {
+                let quantity = containers[ingredient];
+                let percentage = containers_fill[id]
+                    .get(ingredient)
+                    .copied()
+                    .unwrap_or_default();
                 println!(
-                    "\n[{:?} CONTAINER] OF [COFFEE MACHINE {:?}]: {:?}\n",
-                    ingredient, id as i32, quantity
+                    "\n[{:?} CONTAINER] OF [COFFEE MACHINE {:?}]: {} ({:?}%)\n",
+                    ingredient.name(),
+                    id as i32,
+                    get_quantity(units, ingredient, quantity),
+                    percentage
                 );
             }
         }
@@ -45,68 +80,169 @@ pub mod presenter {
     }
 
     /// Shows the current quantity of ingredients consumed between all the containers of all the
-    /// coffee machines.
-    pub fn present_ingredients_consumed(ingredients_consumed: HashMap<String, u32>) {
+    /// coffee machines, in the unit of their containers.
+    pub fn present_ingredients_consumed(
+        ingredients_consumed: HashMap<Ingredient, u32>,
+        units: &HashMap<Ingredient, Unit>,
+    ) {
         println!("\n[INGREDIENTS CONSSUMED]\n");
 
-        for ingredient in INGREDIENTS {
-            let quantity = ingredients_consumed[&ingredient.to_owned()];
-            println!("\nCONSUMPTION OF {:?}: {:?}\n", ingredient, quantity);
+        for ingredient in get_sorted_ingredients(&ingredients_consumed) {
+            let quantity = ingredients_consumed[ingredient];
+            println!(
+                "\nCONSUMPTION OF {:?}: {}\n",
+                ingredient.name(),
+                get_quantity(units, ingredient, quantity)
+            );
+        }
+    }
+
+    /// Shows the quantity of every ingredient that expired and was discarded between all the
+    /// containers of all the coffee machines, in the unit of their containers.
+    pub fn present_ingredients_wasted(
+        containers_waste: &[HashMap<Ingredient, u32>],
+        units: &HashMap<Ingredient, Unit>,
+    ) {
+        let mut ingredients_wasted: HashMap<Ingredient, u32> = HashMap::new();
+        for containers in containers_waste {
+            for (ingredient, wasted) in containers {
+                *ingredients_wasted.entry(ingredient.to_owned()).or_default() += wasted;
+            }
+        }
+        ingredients_wasted.retain(|_, wasted| *wasted > 0);
+        if ingredients_wasted.is_empty() {
+            return;
+        }
+
+        println!("\n[INGREDIENTS WASTED]\n");
+        for ingredient in get_sorted_ingredients(&ingredients_wasted) {
+            let quantity = ingredients_wasted[ingredient];
+            println!(
+                "\nWASTE OF {:?}: {}\n",
+                ingredient.name(),
+                get_quantity(units, ingredient, quantity)
+            );
+        }
+    }
+
+    /// Shows how many times the dispensers of every coffee machine had to wait for an
+    /// ingredient to be replenished.
+    pub fn present_replenish_waits(replenish_waits: Vec<u32>) {
+        for (id, waits) in replenish_waits.iter().enumerate() {
+            println!(
+                "\n[WAITS FOR REPLENISH] OF [COFFEE MACHINE {:?}]: {:?}\n",
+                id as i32, waits
+            );
         }
     }
 
+    /// Shows the average and the greatest time that the orders waited in the queue.
+    pub fn present_queue_wait(report: &Report) -> Result<(), Error> {
+        let (average_ms, max_ms) = report.get_queue_wait_ms()?;
+        println!(
+            "\n[QUEUE WAIT]: AVERAGE {:?} MS - MAX {:?} MS\n",
+            average_ms, max_ms
+        );
+
+        Ok(())
+    }
+
     /// Shows stats of the level of containers of all the coffee machines,
-    /// the total of orders processed and the total of ingredients consumed between
-    /// all the containers of all the coffee machines.
+    /// the total of orders processed, the time the orders waited in the queue, how many
+    /// times the dispensers waited for a replenishment and the total of ingredients
+    /// consumed and wasted between all the containers of all the coffee machines.
+    /// The quantity that expired is discarded first.
     pub fn present_stats(
         coffee_makers: Vec<CoffeeMaker>,
         current_num_orders: u32,
-        initial_quantity: u32,
+        report: &Report,
     ) -> Result<(), Error> {
+        for coffee_maker in &coffee_makers {
+            coffee_maker.containers.discard_expired(coffee_maker.id)?;
+        }
+        let initial_levels = get_initial_levels(coffee_makers.clone());
+        let containers_waste = get_containers_waste_info(coffee_makers.clone());
+        let containers_fill = get_containers_fill_info(coffee_makers.clone());
+        let units = get_units(coffee_makers.clone());
+        let replenish_waits = get_replenish_waits(coffee_makers.clone());
+        let containers_replenished = get_replenished_info(coffee_makers.clone());
         let containers_level = get_containers_info(coffee_makers);
-        present_level_of_containers(containers_level.clone())?;
+        present_level_of_containers(containers_level.clone(), containers_fill, &units)?;
         println!("\n[TOTAL ORDERS PROCESSED]: {:?}\n", current_num_orders);
-        let ingredients_consumed = get_ingredients_consumed(containers_level, initial_quantity);
-        present_ingredients_consumed(ingredients_consumed);
+        present_queue_wait(report)?;
+        present_replenish_waits(replenish_waits);
+        let ingredients_consumed = get_ingredients_consumed_with_waste(
+            containers_level,
+            initial_levels,
+            containers_waste.clone(),
+            containers_replenished,
+        );
+        present_ingredients_consumed(ingredients_consumed, &units);
+        present_ingredients_wasted(&containers_waste, &units);
 
         Ok(())
     }
 
     /// Handles the presentation of stats periodically.
+    /// Returns the number of orders processed that were presented.
     fn present_statistics(
         coffee_makers: Vec<CoffeeMaker>,
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
-    ) -> Result<(), Error> {
+        report: &Report,
+    ) -> Result<i32, Error> {
+        let mut presented = 0;
         let (orders_processed_lock, condvar) = &*orders_processed;
         if let Ok(orders_processed) = orders_processed_lock.lock() {
             println!("[PRESENTER]: WAITING");
             if let Ok(orders_processed) = condvar.wait_while(orders_processed, |num| *num == 0) {
                 println!("[PRESENTER]: PREPARING STATS",);
-                present_stats(
-                    coffee_makers.clone(),
-                    *orders_processed as u32,
-                    INITIAL_QUANTITY * coffee_makers.len() as u32,
-                )?;
+                present_stats(coffee_makers.clone(), *orders_processed as u32, report)?;
+                presented = *orders_processed;
             }
         }
         condvar.notify_all();
 
-        Ok(())
+        Ok(presented)
+    }
+
+    /// Waits the time between two presentations of stats.
+    /// A virtual clock doesn't sleep, so it waits instead until more orders are processed
+    /// or there are no more orders to make, which the dispensers notify.
+    fn wait_to_present(
+        clock: &Clock,
+        orders_processed: &Arc<(Mutex<i32>, Condvar)>,
+        presented: i32,
+        orders: &OrdersQueue,
+    ) {
+        if !clock.is_virtual() {
+            clock.sleep(PRESENT_TIME);
+            return;
+        }
+
+        let (orders_processed_lock, condvar) = &**orders_processed;
+        if let Ok(orders_processed) = orders_processed_lock.lock() {
+            drop(condvar.wait_while(orders_processed, |num| {
+                *num == presented && !orders.is_finished()
+            }));
+        }
     }
 
-    /// Performs the presentation of statistics until there are no more orders to make.
+    /// Performs the presentation of statistics until there are no more orders to make,
+    /// waiting between two presentations the time of the clock received.
     pub fn show_statistics(
         coffee_makers: Vec<CoffeeMaker>,
         orders_processed: Arc<(Mutex<i32>, Condvar)>,
-        orders: Arc<RwLock<Vec<Order>>>,
+        orders: Arc<OrdersQueue>,
+        report: Report,
+        clock: Clock,
     ) -> Result<(), Error> {
         let presenter_handle = thread::spawn(move || loop {
             println!("[PRESENTER]: STARTING");
 
-            match present_statistics(coffee_makers.clone(), orders_processed.clone()) {
-                Ok(_) => {
+            match present_statistics(coffee_makers.clone(), orders_processed.clone(), &report) {
+                Ok(presented) => {
                     println!("[PRESENTER]: FINISHING");
-                    thread::sleep(Duration::from_secs(3))
+                    wait_to_present(&clock, &orders_processed, presented, &orders);
                 }
                 Err(error) => {
                     println!("[PRESENTER]: {:?}", error);
@@ -114,11 +250,9 @@ pub mod presenter {
                 }
             }
 
-            if let Ok(orders) = orders.read() {
-                if orders.is_empty() {
-                    println!("[PRESENTER]: FINISHING SINCE NO MORE ORDERS");
-                    break;
-                }
+            if orders.is_finished() {
+                println!("[PRESENTER]: FINISHING SINCE NO MORE ORDERS");
+                break;
             }
         });
 
@@ -130,29 +264,23 @@ pub mod presenter {
         Ok(())
     }
 
-    /// Shows an alert if the level of grain coffee container, the milk container or the cocoa container
-    /// is less than quarter of its initial capacity.
+    /// Sends an alert to the sinks of the alerter every time the level of the container of
+    /// an ingredient drops to one of the alert levels of its coffee machine, when its alert
+    /// flag is set. Once the dispensers finished, the flags still set are checked before
+    /// finishing.
     pub fn show_alert_of_capacity(
-        orders: Arc<RwLock<Vec<Order>>>,
         containers: Containers,
-        coffee_maker_id: u32,
         has_to_alert: Arc<(Mutex<Vec<bool>>, Condvar)>,
-        values: HashMap<i32, String>,
+        mut alerter: Alerter,
+        dispensers_finished: Arc<AtomicBool>,
     ) -> Result<(), Error> {
+        let coffee_maker_id = alerter.coffee_maker_id;
         let handle = thread::spawn(move || loop {
             println!(
                 "[ALERTER] OF [COFFEE MAKER {:?}]: STARTING",
                 coffee_maker_id
             );
-            if let Ok(orders) = orders.read() {
-                if orders.is_empty() {
-                    println!(
-                        "[ALERTER] OF [COFFEE MAKER {:?}]: FINISHING SINCE NO MORE ORDERS",
-                        coffee_maker_id
-                    );
-                    break;
-                }
-            }
+            let finished = dispensers_finished.load(Ordering::SeqCst);
 
             let (has_to_alert_lock, condvar) = &*has_to_alert;
             if let Ok(has_to_alert) = has_to_alert_lock.lock() {
@@ -161,19 +289,19 @@ pub mod presenter {
                     coffee_maker_id, has_to_alert
                 );
                 if let Ok(mut has_to_alert) =
-                    condvar.wait_while(has_to_alert, |v| v.iter().all(|&b| !b))
+                    condvar.wait_while(has_to_alert, |v| !finished && v.iter().all(|&b| !b))
                 {
                     println!(
                         "[ALERTER] OF [COFFEE MAKER {:?}]: PREPARING ALARM {:?}",
                         coffee_maker_id, has_to_alert
                     );
-                    let containers_level = get_quantity_of(containers.clone());
-                    for i in 0..3 {
-                        if has_to_alert[i] {
-                            let ingredient = &values[&(i as i32)];
-                            let value = containers_level[ingredient];
-                            if value == VALUE_TO_ALERT {
-                                println!("\n[ALERTER] OF [COFFEE MAKER {:?}]: THE LEVEL OF THE CONTAINER OF {:?} IS {:?}\n", coffee_maker_id, ingredient, value);
+                    let ingredients = alerter.get_ingredients().to_vec();
+                    for (i, ingredient) in ingredients.iter().enumerate() {
+                        if has_to_alert.get(i).copied().unwrap_or_default() {
+                            if let Ok(Some(alert)) =
+                                alerter.check_container(&containers, ingredient)
+                            {
+                                alerter.send(&alert);
                             }
                             has_to_alert[i] = false;
                         }
@@ -181,6 +309,14 @@ pub mod presenter {
                 }
             }
             condvar.notify_all();
+
+            if finished {
+                println!(
+                    "[ALERTER] OF [COFFEE MAKER {:?}]: FINISHING SINCE NO MORE ORDERS",
+                    coffee_maker_id
+                );
+                break;
+            }
         });
 
         match handle.join() {
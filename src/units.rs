@@ -0,0 +1,129 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+/// Unit in which the quantity of an ingredient is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Unit {
+    #[serde(rename = "g")]
+    Grams,
+    #[serde(rename = "ml")]
+    Milliliters,
+    #[serde(rename = "shots", alias = "shot")]
+    Shots,
+}
+
+impl Unit {
+    /// Returns the unit with the symbol received, singular or plural,
+    /// returns an error if it is unknown.
+    pub fn from_symbol(symbol: &str) -> Result<Unit, Error> {
+        match symbol.to_lowercase().as_str() {
+            "g" => Ok(Unit::Grams),
+            "ml" => Ok(Unit::Milliliters),
+            "shot" | "shots" => Ok(Unit::Shots),
+            _ => Err(Error::UnknownUnit(symbol.to_owned())),
+        }
+    }
+
+    /// Returns the symbol of the unit.
+    pub fn symbol(&self) -> &str {
+        match self {
+            Unit::Grams => "g",
+            Unit::Milliliters => "ml",
+            Unit::Shots => "shots",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// A quantity of an ingredient in a unit, written as its amount followed by the symbol of
+/// its unit, like "30 ml" or "1 shot".
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(into = "String")]
+pub struct Quantity {
+    pub amount: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// Creates a new [`Quantity`] with the amount and the unit received.
+    pub fn new(amount: f64, unit: Unit) -> Quantity {
+        Quantity { amount, unit }
+    }
+
+    /// Converts the quantity from a text like "30 ml" if it can,
+    /// returns an error if not. The amount can't be negative.
+    pub fn parse(text: &str) -> Result<Quantity, Error> {
+        let text = text.trim();
+        let unit_start = text
+            .find(|character: char| !character.is_ascii_digit() && character != '.')
+            .unwrap_or(text.len());
+        let (amount, unit) = text.split_at(unit_start);
+        let unit = unit.trim();
+        if amount.is_empty() || unit.is_empty() {
+            return Err(Error::WrongQuantity(text.to_owned()));
+        }
+
+        match amount.parse::<f64>() {
+            Ok(amount) if amount.is_finite() => Ok(Quantity::new(amount, Unit::from_symbol(unit)?)),
+            _ => Err(Error::WrongQuantity(text.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.unit)
+    }
+}
+
+impl From<Quantity> for String {
+    fn from(quantity: Quantity) -> Self {
+        quantity.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::Error,
+        units::{Quantity, Unit},
+    };
+
+    #[test]
+    fn test01_parse_a_quantity_with_its_unit() {
+        assert_eq!(
+            Quantity::parse("30 ml"),
+            Ok(Quantity::new(30.0, Unit::Milliliters))
+        );
+        assert_eq!(
+            Quantity::parse("1 shot"),
+            Ok(Quantity::new(1.0, Unit::Shots))
+        );
+        assert_eq!(Quantity::parse("2.5g"), Ok(Quantity::new(2.5, Unit::Grams)));
+        assert_eq!(Quantity::new(10.0, Unit::Grams).to_string(), "10 g");
+    }
+
+    #[test]
+    fn test02_parse_a_quantity_without_unit_or_with_an_unknown_one_returns_an_error() {
+        assert_eq!(
+            Quantity::parse("30"),
+            Err(Error::WrongQuantity("30".to_string()))
+        );
+        assert_eq!(
+            Quantity::parse("-1 shot"),
+            Err(Error::WrongQuantity("-1 shot".to_string()))
+        );
+        assert_eq!(
+            Quantity::parse("2 cups"),
+            Err(Error::UnknownUnit("cups".to_string()))
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    ingredients::{Ingredient, IngredientRegistry},
+    orders::{Order, OrderQuantity},
+};
+
+const ID: &str = "id";
+const PRIORITY: &str = "priority";
+const ARRIVAL_MS: &str = "arrival_ms";
+const RECIPE: &str = "recipe";
+
+/// A problem found in an order of an orders file, with its position in the file.
+/// The line and column start at 1, the order is the index of the order in its file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub line: usize,
+    pub column: usize,
+    pub order: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Creates a new [`ValidationError`] of the field received, without position.
+    pub fn new(field: Option<&str>, message: String) -> ValidationError {
+        ValidationError {
+            line: 0,
+            column: 0,
+            order: None,
+            field: field.map(|field| field.to_owned()),
+            message,
+        }
+    }
+
+    /// Returns the same [`ValidationError`] at the position received.
+    pub fn at(mut self, line: usize, column: usize, order: Option<usize>) -> ValidationError {
+        self.line = line;
+        self.column = column;
+        self.order = order;
+        self
+    }
+}
+
+/// Checks the fields of the orders and that the coffee makers can make them.
+/// The quantities with a unit are converted to the unit of the containers of its registry.
+#[derive(Debug, Clone, Default)]
+pub struct OrderValidator {
+    max_quantities: HashMap<Ingredient, u32>,
+    registry: IngredientRegistry,
+}
+
+impl OrderValidator {
+    /// Creates a new [`OrderValidator`] with the greatest quantity of every container.
+    /// If there are no greatest quantities, any ingredient in any quantity is valid.
+    pub fn new(max_quantities: HashMap<Ingredient, u32>) -> OrderValidator {
+        OrderValidator {
+            max_quantities,
+            registry: IngredientRegistry::new(),
+        }
+    }
+
+    /// Creates a new [`OrderValidator`] where the greatest quantity of every container is
+    /// the greatest quantity that it can hold in any coffee maker, and the ingredients
+    /// added in the configuration are measured in the unit of their containers.
+    pub fn from_config(config: &Config) -> OrderValidator {
+        let mut max_quantities: HashMap<Ingredient, u32> = HashMap::new();
+        let mut registry = IngredientRegistry::new();
+        for coffee_maker in &config.coffee_makers {
+            let coffee_maker_registry = coffee_maker.get_registry();
+            for container in coffee_maker.get_ingredients() {
+                registry.register_with_unit(
+                    container.name(),
+                    coffee_maker_registry.get_unit(&container),
+                );
+                let max_quantity = coffee_maker.get_capacity(&container);
+                let quantity = max_quantities.entry(container).or_default();
+                *quantity = max_quantity.max(*quantity);
+            }
+        }
+
+        OrderValidator {
+            max_quantities,
+            registry,
+        }
+    }
+
+    /// Returns the registry with the units of the containers that the quantities are
+    /// converted to.
+    pub fn get_registry(&self) -> &IngredientRegistry {
+        &self.registry
+    }
+
+    /// Returns true if the value is a quantity of an ingredient.
+    fn is_quantity(value: &Value) -> bool {
+        value
+            .as_u64()
+            .is_some_and(|quantity| quantity <= u32::MAX as u64)
+    }
+
+    /// Returns the problem of the quantity of the ingredient received, if it has one.
+    /// It can be a non negative integer or a text with a unit that can be converted to the
+    /// unit of the container of the ingredient.
+    fn check_quantity(&self, ingredient: &str, quantity: &Value) -> Option<ValidationError> {
+        if OrderValidator::is_quantity(quantity) {
+            return None;
+        }
+        let message = match serde_json::from_value::<OrderQuantity>(quantity.clone()) {
+            Ok(with_unit @ OrderQuantity::WithUnit(_)) => {
+                match with_unit.get_value(ingredient, &self.registry) {
+                    Ok(_) => return None,
+                    Err(err) => format!(
+                        "{} is not a quantity of {} ({:?})",
+                        quantity, ingredient, err
+                    ),
+                }
+            }
+            _ => format!("{} is not a non negative integer", quantity),
+        };
+
+        Some(ValidationError::new(Some(ingredient), message))
+    }
+
+    /// Returns the problems of the fields of an order written in json.
+    /// Every field but the id, the priority, the arrival time and the recipe is the quantity
+    /// of an ingredient.
+    pub fn check_fields(&self, order: &Value) -> Vec<ValidationError> {
+        let Some(fields) = order.as_object() else {
+            return vec![ValidationError::new(
+                None,
+                "the order is not an object".to_owned(),
+            )];
+        };
+
+        let mut errors = Vec::new();
+        for field in [ID, PRIORITY] {
+            if fields
+                .get(field)
+                .is_some_and(|value| !OrderValidator::is_quantity(value))
+            {
+                errors.push(ValidationError::new(
+                    Some(field),
+                    format!("the {} is not a non negative integer", field),
+                ));
+            }
+        }
+        if fields.get(ARRIVAL_MS).is_some_and(|value| !value.is_u64()) {
+            errors.push(ValidationError::new(
+                Some(ARRIVAL_MS),
+                "the arrival time is not a non negative number of milliseconds".to_owned(),
+            ));
+        }
+
+        if let Some(recipe) = fields.get(RECIPE) {
+            if !recipe.is_string() {
+                errors.push(ValidationError::new(
+                    Some(RECIPE),
+                    "the recipe is not a name".to_owned(),
+                ));
+            }
+            return errors;
+        }
+
+        for (ingredient, quantity) in fields {
+            if [ID, PRIORITY, ARRIVAL_MS].contains(&ingredient.as_str()) {
+                continue;
+            }
+            if let Some(error) = self.check_quantity(ingredient, quantity) {
+                errors.push(error);
+            }
+        }
+
+        errors
+    }
+
+    /// Converts the quantities with a unit of an order written in json to numbers in the
+    /// unit of their containers. The quantities that can't be converted are kept.
+    pub fn convert_quantities(&self, mut order: Value) -> Value {
+        let Some(fields) = order.as_object_mut() else {
+            return order;
+        };
+        for (ingredient, quantity) in fields.iter_mut() {
+            if [ID, PRIORITY, ARRIVAL_MS, RECIPE].contains(&ingredient.as_str()) {
+                continue;
+            }
+            if let Value::String(text) = quantity {
+                if let Ok(value) =
+                    OrderQuantity::WithUnit(text.clone()).get_value(ingredient, &self.registry)
+                {
+                    *quantity = Value::from(value);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Returns the problems of an order that can't be made: an order without ingredients,
+    /// with an ingredient that has no container or with more of an ingredient than any
+    /// container can hold.
+    pub fn check_order(&self, order: &Order) -> Vec<ValidationError> {
+        let mut quantities: Vec<(&String, &u32)> = order.ingredients.iter().collect();
+        quantities.sort();
+
+        let mut errors = Vec::new();
+        if quantities.iter().all(|(_, quantity)| **quantity == 0) {
+            errors.push(ValidationError::new(
+                None,
+                "the order has no ingredients".to_owned(),
+            ));
+        }
+        if self.max_quantities.is_empty() {
+            return errors;
+        }
+        for (ingredient, quantity) in quantities {
+            match self
+                .max_quantities
+                .get(&Ingredient::from_order_name(ingredient))
+            {
+                None => errors.push(ValidationError::new(
+                    Some(ingredient),
+                    "no coffee maker has a container of this ingredient".to_owned(),
+                )),
+                Some(max_quantity) if quantity > max_quantity => errors.push(ValidationError::new(
+                    Some(ingredient),
+                    format!(
+                        "{} is more than any container can hold ({})",
+                        quantity, max_quantity
+                    ),
+                )),
+                _ => {}
+            }
+        }
+
+        errors
+    }
+}
+
+/// Returns the line and column (starting at 1) of the byte offset of the text received.
+pub fn get_position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(idx) => offset - idx,
+        None => offset + 1,
+    };
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use crate::{
+        config::Config,
+        ingredients::Ingredient,
+        orders::Order,
+        validation::{get_position, OrderValidator, ValidationError},
+    };
+
+    #[test]
+    fn test01_an_order_with_wrong_fields_has_a_problem_for_every_field() {
+        let validator = OrderValidator::default();
+        let order = json!({"id": -1, "coffee": 10, "water": "ten", "sugar": -2});
+        let errors = validator.check_fields(&order);
+        let fields: Vec<Option<String>> = errors.into_iter().map(|error| error.field).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                Some("id".to_string()),
+                Some("sugar".to_string()),
+                Some("water".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test02_an_order_with_a_recipe_does_not_need_ingredients() {
+        let validator = OrderValidator::default();
+
+        assert!(validator
+            .check_fields(&json!({"recipe": "latte"}))
+            .is_empty());
+        assert_eq!(validator.check_fields(&json!({"recipe": 3})).len(), 1);
+        assert_eq!(validator.check_fields(&json!([1, 2])).len(), 1);
+    }
+
+    #[test]
+    fn test03_an_order_without_ingredients_has_a_problem() {
+        let validator = OrderValidator::default();
+        let errors = validator.check_order(&Order::new(0, 0, 0, 0));
+
+        assert_eq!(
+            errors,
+            vec![ValidationError::new(
+                None,
+                "the order has no ingredients".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test04_an_order_with_more_than_any_container_can_hold_has_a_problem() {
+        let mut max_quantities = HashMap::new();
+        for container in [Ingredient::Coffee, Ingredient::Cocoa, Ingredient::Foam] {
+            max_quantities.insert(container, 1000);
+        }
+        max_quantities.insert(Ingredient::HotWater, 100);
+        let validator = OrderValidator::new(max_quantities);
+        let errors = validator.check_order(&Order::new(500, 101, 1, 1));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, Some("water".to_string()));
+    }
+
+    #[test]
+    fn test05_the_greatest_quantities_are_the_ones_of_the_biggest_containers() {
+        let validator = OrderValidator::from_config(&Config::default());
+
+        assert!(validator
+            .check_order(&Order::new(100, 100, 100, 100))
+            .is_empty());
+        assert_eq!(validator.check_order(&Order::new(101, 1, 1, 1)).len(), 1);
+    }
+
+    #[test]
+    fn test06_an_order_with_an_ingredient_without_container_has_a_problem() {
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"sugar\": 30}}]}";
+        let validator =
+            OrderValidator::from_config(&Config::deserialize(config).expect("Valid config"));
+        let mut ingredients = HashMap::new();
+        ingredients.insert("sugar".to_string(), 10);
+        ingredients.insert("tea".to_string(), 10);
+        let errors = validator.check_order(&Order::from(ingredients));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, Some("tea".to_string()));
+    }
+
+    #[test]
+    fn test07_get_the_line_and_column_of_an_offset() {
+        let text = "[\n  {\"coffee\": 1},\n  {\"coffee\": 2}\n]";
+
+        assert_eq!(get_position(text, 0), (1, 1));
+        assert_eq!(get_position(text, 4), (2, 3));
+        assert_eq!(get_position(text, 21), (3, 3));
+    }
+
+    #[test]
+    fn test08_an_order_with_quantities_in_other_units_has_a_problem_if_they_cant_be_converted() {
+        let validator = OrderValidator::default();
+        let order =
+            json!({"coffee": "1 shot", "water": "30 ml", "cocoa": "1 shot", "foam": "2 cups"});
+        let errors = validator.check_fields(&order);
+        let fields: Vec<Option<String>> = errors.iter().map(|error| error.field.clone()).collect();
+
+        assert_eq!(
+            fields,
+            vec![Some("cocoa".to_string()), Some("foam".to_string())]
+        );
+        assert_eq!(
+            errors[0].message,
+            "\"1 shot\" is not a quantity of cocoa (CantConvertUnit(\"cocoa\", Shots))"
+        );
+    }
+
+    #[test]
+    fn test09_an_ingredient_of_the_configuration_can_be_ordered_in_the_unit_of_its_container() {
+        let config = "{\"coffee_makers\": [{\"id\": 0, \"containers\": {\"tea\": 100}, \"units\": {\"tea\": \"ml\"}}]}";
+        let validator =
+            OrderValidator::from_config(&Config::deserialize(config).expect("Valid config"));
+
+        assert!(validator.check_fields(&json!({"tea": "10 ml"})).is_empty());
+        assert_eq!(validator.check_fields(&json!({"tea": "10 g"})).len(), 1);
+        assert_eq!(
+            validator.convert_quantities(json!({"id": 3, "tea": "10 ml", "coffee": "1 shot"})),
+            json!({"id": 3, "tea": 10, "coffee": 7})
+        );
+    }
+}